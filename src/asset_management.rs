@@ -1,18 +1,47 @@
 use anyhow::Result;
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
-use sulphur_core::{Asset, GameData, Iwad, Mod, Movable};
+use sulphur_core::{Asset, GameData, Instance, Iwad, Mod, Movable};
 
-use crate::file_utils::select_file;
+use crate::cli_config::{storage_dir, Hooks, InstanceExtras, Patch};
+use crate::file_utils::{move_into, select_file};
+use crate::input::{Confirm, Input, Select};
 use crate::menu::{Menu, BACK_BUTTON};
 
-pub trait AssetCollection<T: Movable + AsMut<Asset>> {
+/// The instance's enabled IWADs, in load order.
+pub fn enabled_iwads(instance: &Instance) -> impl Iterator<Item = &Asset> {
+    instance
+        .gamedata
+        .iwads
+        .iter()
+        .map(|iwad| &iwad.0)
+        .filter(|asset| asset.enabled)
+}
+
+/// The instance's enabled mods, in load order.
+pub fn enabled_mods(instance: &Instance) -> impl Iterator<Item = &Asset> {
+    instance
+        .gamedata
+        .mods
+        .iter()
+        .map(|mod_| &mod_.0)
+        .filter(|asset| asset.enabled)
+}
+
+/// Every WAD the engine loads for the instance: its enabled IWADs, then its
+/// enabled mods.
+pub fn enabled_wads(instance: &Instance) -> impl Iterator<Item = &Asset> {
+    enabled_iwads(instance).chain(enabled_mods(instance))
+}
+
+pub trait AssetCollection<T: AsMut<Asset>> {
     fn get_assets(&self) -> &Vec<T>;
     fn get_assets_mut(&mut self) -> &mut Vec<T>;
     fn create_asset(&self, path: PathBuf) -> T;
     fn get_asset_name(&self, asset: &T) -> String;
     fn get_asset_mut(&mut self, index: usize) -> &mut Asset;
     fn get_asset_state(&self, asset: &T) -> bool;
+    fn move_asset(&mut self, index: usize) -> Result<()>;
 }
 
 impl AssetCollection<Iwad> for GameData {
@@ -25,12 +54,10 @@ impl AssetCollection<Iwad> for GameData {
     }
 
     fn create_asset(&self, path: PathBuf) -> Iwad {
-        Iwad {
-            0: Asset {
-                path,
-                enabled: true,
-            },
-        }
+        Iwad(Asset {
+            path,
+            enabled: true,
+        })
     }
 
     fn get_asset_name(&self, asset: &Iwad) -> String {
@@ -50,6 +77,11 @@ impl AssetCollection<Iwad> for GameData {
     fn get_asset_state(&self, asset: &Iwad) -> bool {
         asset.0.enabled
     }
+
+    fn move_asset(&mut self, index: usize) -> Result<()> {
+        self.iwads[index].move_file(false)?;
+        Ok(())
+    }
 }
 
 impl AssetCollection<Mod> for GameData {
@@ -62,12 +94,10 @@ impl AssetCollection<Mod> for GameData {
     }
 
     fn create_asset(&self, path: PathBuf) -> Mod {
-        Mod {
-            0: Asset {
-                path,
-                enabled: true,
-            },
-        }
+        Mod(Asset {
+            path,
+            enabled: true,
+        })
     }
 
     fn get_asset_name(&self, asset: &Mod) -> String {
@@ -87,19 +117,62 @@ impl AssetCollection<Mod> for GameData {
     fn get_asset_state(&self, asset: &Mod) -> bool {
         asset.0.enabled
     }
+
+    fn move_asset(&mut self, index: usize) -> Result<()> {
+        self.mods[index].move_file(false)?;
+        Ok(())
+    }
 }
 
-fn asset_management<T: Movable + AsMut<Asset>>(
-    game_data: &mut GameData,
+impl AssetCollection<Patch> for InstanceExtras {
+    fn get_assets(&self) -> &Vec<Patch> {
+        &self.patches
+    }
+
+    fn get_assets_mut(&mut self) -> &mut Vec<Patch> {
+        &mut self.patches
+    }
+
+    fn create_asset(&self, path: PathBuf) -> Patch {
+        Patch(Asset {
+            path,
+            enabled: true,
+        })
+    }
+
+    fn get_asset_name(&self, asset: &Patch) -> String {
+        asset
+            .0
+            .path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn get_asset_mut(&mut self, index: usize) -> &mut Asset {
+        &mut self.patches[index].0
+    }
+
+    fn get_asset_state(&self, asset: &Patch) -> bool {
+        asset.0.enabled
+    }
+
+    fn move_asset(&mut self, index: usize) -> Result<()> {
+        let asset = &mut self.patches[index].0;
+        asset.path = move_into(&asset.path, &storage_dir("patches")?)?;
+        Ok(())
+    }
+}
+
+fn asset_management<T: AsMut<Asset>, C: AssetCollection<T>>(
+    game_data: &mut C,
     menu: Menu,
     file_prompt: &str,
     extensions: &[&str],
     toggle_prompt: &str,
     remove_prompt: &str,
-) -> Result<()>
-where
-    GameData: AssetCollection<T>,
-{
+) -> Result<()> {
     let mut has_entered_loop = false;
     loop {
         let names: Vec<String> = game_data
@@ -118,9 +191,9 @@ where
             })
             .collect();
         let selection = if !game_data.get_assets().is_empty() || has_entered_loop {
-            Select::with_theme(&ColorfulTheme::default())
+            Select::new()
                 .with_prompt("Choose Action")
-                .items(&menu.options())
+                .items(menu.options())
                 .default(0)
                 .interact()?
         } else {
@@ -134,13 +207,13 @@ where
                 if let Some(path) = select_file(file_prompt, false, Some(extensions), None)? {
                     let asset = game_data.create_asset(path);
                     game_data.get_assets_mut().push(asset);
-                    if Confirm::with_theme(&ColorfulTheme::default())
+                    if Confirm::new()
                         .with_prompt("Do you want to move the file?")
                         .default(true)
                         .interact()?
                     {
                         // Since we're adding an element to the vector, the old length is the last element
-                        game_data.get_assets_mut()[names.len()].move_file(false)?;
+                        game_data.move_asset(names.len())?;
                     }
                 }
             }
@@ -153,7 +226,7 @@ where
                 let mut toggle_names = names.clone();
                 toggle_names.push(BACK_BUTTON.to_string());
 
-                let selection_index = Select::with_theme(&ColorfulTheme::default())
+                let selection_index = Select::new()
                     .with_prompt(toggle_prompt)
                     .items(&toggle_names)
                     .default(0)
@@ -180,7 +253,7 @@ where
                 let mut remove_names = names.clone();
                 remove_names.push(BACK_BUTTON.to_string());
 
-                let selection_index = Select::with_theme(&ColorfulTheme::default())
+                let selection_index = Select::new()
                     .with_prompt(remove_prompt)
                     .items(&remove_names)
                     .default(0)
@@ -192,8 +265,8 @@ where
 
                 let selected_name = &remove_names[selection_index];
 
-                if Confirm::with_theme(&ColorfulTheme::default())
-                    .with_prompt(&format!(
+                if Confirm::new()
+                    .with_prompt(format!(
                         "Are you sure you want to delete '{}'?",
                         selected_name
                     ))
@@ -213,7 +286,7 @@ where
 }
 
 pub fn iwad_management(game_data: &mut GameData) -> Result<()> {
-    asset_management::<Iwad>(
+    asset_management::<Iwad, _>(
         game_data,
         Menu::IwadManagementMenu,
         "Select IWAD file",
@@ -224,7 +297,7 @@ pub fn iwad_management(game_data: &mut GameData) -> Result<()> {
 }
 
 pub fn mod_management(game_data: &mut GameData) -> Result<()> {
-    asset_management::<Mod>(
+    asset_management::<Mod, _>(
         game_data,
         Menu::ModManagementMenu,
         "Select Mod file",
@@ -234,16 +307,27 @@ pub fn mod_management(game_data: &mut GameData) -> Result<()> {
     )
 }
 
+pub fn patch_management(extras: &mut InstanceExtras) -> Result<()> {
+    asset_management::<Patch, _>(
+        extras,
+        Menu::PatchManagementMenu,
+        "Select DeHackEd patch",
+        &["deh", "bex"],
+        "Select patch to toggle",
+        "Select patch to remove",
+    )
+}
+
 pub fn additional_params_management(game_data: &mut GameData) -> Result<()> {
     loop {
-        match Select::with_theme(&ColorfulTheme::default())
+        match Select::new()
             .with_prompt("Choose Action")
-            .items(&Menu::AdditionalParamsMenu.options())
+            .items(Menu::AdditionalParamsMenu.options())
             .default(0)
             .interact()?
         {
             0 => {
-                let input: String = Input::with_theme(&ColorfulTheme::default())
+                let input: String = Input::new()
                     .with_prompt("Enter new parameter")
                     .interact_text()?;
                 game_data.additional_params.push(input.into());
@@ -261,7 +345,7 @@ pub fn additional_params_management(game_data: &mut GameData) -> Result<()> {
                     .collect();
                 param_list.push(BACK_BUTTON.to_string());
 
-                let selection = Select::with_theme(&ColorfulTheme::default())
+                let selection = Select::new()
                     .with_prompt("Select parameter to remove")
                     .items(&param_list)
                     .default(0)
@@ -280,3 +364,160 @@ pub fn additional_params_management(game_data: &mut GameData) -> Result<()> {
         }
     }
 }
+
+/// Names `env` accepts as variables: `[A-Za-z_][A-Za-z0-9_]*`.
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn launch_environment_management(
+    wrappers: &mut Vec<String>,
+    env: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    loop {
+        if !wrappers.is_empty() {
+            println!("Wrappers: {}", wrappers.join(" "));
+        }
+        for (key, value) in env.iter() {
+            println!("{}={}", key, value);
+        }
+
+        match Select::new()
+            .with_prompt("Choose Action")
+            .items(Menu::LaunchEnvironmentMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => {
+                let input: String = Input::new()
+                    .with_prompt("Enter wrapper command (e.g. gamemoderun, taskset -c 0-3)")
+                    .interact_text()?;
+                wrappers.push(input.trim().to_string());
+            }
+            1 => {
+                if wrappers.is_empty() {
+                    println!("No wrappers to remove!");
+                    continue;
+                }
+
+                let mut wrapper_list = wrappers.clone();
+                wrapper_list.push(BACK_BUTTON.to_string());
+
+                let selection = Select::new()
+                    .with_prompt("Select wrapper to remove")
+                    .items(&wrapper_list)
+                    .default(0)
+                    .interact()?;
+
+                if selection < wrappers.len() {
+                    wrappers.remove(selection);
+                }
+            }
+            2 => {
+                let key: String = Input::new()
+                    .with_prompt("Enter variable name")
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        if is_valid_env_name(input) {
+                            Ok(())
+                        } else {
+                            Err("Use only letters, numbers and '_', not starting with a number")
+                        }
+                    })
+                    .interact_text()?;
+                let value: String = Input::new()
+                    .with_prompt(format!("Enter value for {}", key))
+                    .default(env.get(&key).cloned().unwrap_or_default())
+                    .allow_empty(true)
+                    .interact_text()?;
+                env.insert(key, value);
+            }
+            3 => {
+                if env.is_empty() {
+                    println!("No environment variables to remove!");
+                    continue;
+                }
+
+                let mut variable_list: Vec<String> = env.keys().cloned().collect();
+                variable_list.push(BACK_BUTTON.to_string());
+
+                let selection = Select::new()
+                    .with_prompt("Select variable to remove")
+                    .items(&variable_list)
+                    .default(0)
+                    .interact()?;
+
+                if selection < env.len() {
+                    env.remove(&variable_list[selection]);
+                }
+            }
+            4 => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+pub fn hooks_management(hooks: &mut Hooks) -> Result<()> {
+    loop {
+        for hook in &hooks.pre_launch {
+            println!("Before launch: {}", hook);
+        }
+        for hook in &hooks.post_exit {
+            println!("After exit: {}", hook);
+        }
+
+        match Select::new()
+            .with_prompt("Choose Action")
+            .items(Menu::HooksMenu.options())
+            .default(0)
+            .interact()?
+        {
+            selection @ (0 | 1) => {
+                let input: String = Input::new()
+                    .with_prompt("Enter shell command")
+                    .interact_text()?;
+                if selection == 0 {
+                    hooks.pre_launch.push(input);
+                } else {
+                    hooks.post_exit.push(input);
+                }
+            }
+            2 => {
+                if hooks.pre_launch.is_empty() && hooks.post_exit.is_empty() {
+                    println!("No hooks to remove!");
+                    continue;
+                }
+
+                let mut hook_list: Vec<String> = hooks
+                    .pre_launch
+                    .iter()
+                    .map(|hook| format!("Before launch: {}", hook))
+                    .chain(
+                        hooks
+                            .post_exit
+                            .iter()
+                            .map(|hook| format!("After exit: {}", hook)),
+                    )
+                    .collect();
+                hook_list.push(BACK_BUTTON.to_string());
+
+                let selection = Select::new()
+                    .with_prompt("Select hook to remove")
+                    .items(&hook_list)
+                    .default(0)
+                    .interact()?;
+
+                if selection < hooks.pre_launch.len() {
+                    hooks.pre_launch.remove(selection);
+                } else if selection < hook_list.len() - 1 {
+                    hooks.post_exit.remove(selection - hooks.pre_launch.len());
+                }
+            }
+            3 => return Ok(()),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use sulphur_core::{Asset, GameData};
+
+use crate::duration_utils::DurationStyle;
+use crate::image_utils::ImageProtocol;
+use crate::instance_diff::describe_changes;
+use crate::list_view::ListView;
+use crate::time_utils::{TimeDisplay, DEFAULT_DATE_FORMAT};
+
+const XDG_PREFIX: &str = "sulphur";
+const CLI_CONFIG_FILE: &str = "cli.toml";
+
+/// Settings and per-instance data that only the CLI knows about.
+/// Stored next to the core config so that `SulphurConfig` stays untouched.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct CliConfig {
+    pub settings: CliSettings,
+    pub instances: BTreeMap<String, InstanceExtras>,
+    pub templates: BTreeMap<String, Template>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct CliSettings {
+    /// Pass DeHackEd patches with `-file` instead of `-deh`/`-bex`.
+    pub patches_as_file: bool,
+    /// Wrapper commands applied to every instance, outermost first.
+    pub wrappers: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    /// Hooks run for every instance, before the per-instance ones.
+    pub hooks: Hooks,
+    /// Show one line per instance once the list grows past this many entries.
+    pub compact_list_threshold: Option<usize>,
+    pub image_protocol: ImageProtocol,
+    pub idle_policy: IdlePolicy,
+    pub duration_style: DurationStyle,
+    pub time_display: TimeDisplay,
+    /// strftime format for dates, `DEFAULT_DATE_FORMAT` when unset.
+    pub date_format: Option<String>,
+    pub list_view: ListView,
+}
+
+/// How much of a session counts towards playtime. Input to the engine window
+/// isn't watched, so a running but paused game only stops counting at the cap.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct IdlePolicy {
+    /// Longest time a single session may count for.
+    pub max_session: Option<Duration>,
+    /// Leave out the time the engine process spent stopped, e.g. by SIGSTOP.
+    pub exclude_stopped: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct InstanceExtras {
+    pub patches: Vec<Patch>,
+    /// Engine invocation used instead of the global GZDoom command.
+    pub engine: Option<String>,
+    /// Version reported by the engine at the last launch.
+    pub engine_version: Option<String>,
+    /// Wrapper commands applied inside the global ones, outermost first.
+    pub wrappers: Vec<String>,
+    /// Environment variables set on top of the global ones.
+    pub env: BTreeMap<String, String>,
+    pub hooks: Hooks,
+    pub tags: Vec<String>,
+    /// Favourites are pinned to the top of the instance list.
+    pub favourite: bool,
+    pub group: Option<String>,
+    pub notes: String,
+    /// Archived instances are hidden from the instance list but keep their
+    /// playtime and saves.
+    pub archived: bool,
+    pub created: Option<SystemTime>,
+    /// Every launch of the instance, oldest first.
+    pub sessions: Vec<SessionRecord>,
+    /// Saved edits, oldest first.
+    pub history: Vec<Revision>,
+}
+
+/// A saved edit of an instance, keeping the game data from before it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Revision {
+    pub saved_at: SystemTime,
+    pub changes: Vec<String>,
+    pub previous: GameData,
+}
+
+/// A single play session.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionRecord {
+    pub started_at: SystemTime,
+    /// Time counted towards playtime after applying the idle policy.
+    pub duration: Duration,
+    /// Wall-clock time the engine ran. Missing in sessions recorded before
+    /// idle policies existed.
+    pub raw_duration: Option<Duration>,
+    /// `None` when the engine was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// Map given on the command line with `+map` or `-warp`, if any.
+    pub map: Option<String>,
+}
+
+/// Shell commands run around a launch. They receive `SULPHUR_INSTANCE_NAME` and
+/// `SULPHUR_SAVE_DIR`; post-exit hooks also get `SULPHUR_EXIT_CODE` and
+/// `SULPHUR_SESSION_SECONDS`.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct Hooks {
+    pub pre_launch: Vec<String>,
+    pub post_exit: Vec<String>,
+}
+
+impl CliSettings {
+    pub fn format_time(&self, time: SystemTime) -> String {
+        let date_format = self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+        self.time_display.format(time, date_format)
+    }
+}
+
+impl InstanceExtras {
+    const MAX_REVISIONS: usize = 50;
+
+    /// Records a saved edit if it changed the game data.
+    pub fn record_revision(&mut self, previous: &GameData, current: &GameData) {
+        let changes = describe_changes(previous, current);
+        if changes.is_empty() {
+            return;
+        }
+        self.history.push(Revision {
+            saved_at: SystemTime::now(),
+            changes,
+            previous: previous.clone(),
+        });
+        if self.history.len() > Self::MAX_REVISIONS {
+            self.history.remove(0);
+        }
+    }
+
+    /// The parts describing how the instance is set up, without anything
+    /// recorded while playing it.
+    pub fn setup(&self) -> Self {
+        Self {
+            engine_version: None,
+            archived: false,
+            created: None,
+            sessions: vec![],
+            history: vec![],
+            ..self.clone()
+        }
+    }
+}
+
+/// A reusable instance setup. `{name}` in the save directory and parameters
+/// is replaced with the name of the instance created from it.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Template {
+    pub gamedata: GameData,
+    pub extras: InstanceExtras,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Patch(pub Asset);
+
+impl AsMut<Asset> for Patch {
+    fn as_mut(&mut self) -> &mut Asset {
+        &mut self.0
+    }
+}
+
+impl CliConfig {
+    /// Reads the config at `path`, or an empty one if there is none yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn extras(&self, name: &str) -> InstanceExtras {
+        self.instances.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Stores `extras` under `new_name`, dropping the entry kept under `old_name`.
+    /// Refuses to take over the entry of another instance called `new_name`.
+    pub fn set_extras(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+        extras: InstanceExtras,
+    ) -> Result<()> {
+        if old_name != new_name && self.instances.contains_key(new_name) {
+            bail!("an instance named '{}' already exists", new_name);
+        }
+        self.instances.remove(old_name);
+        self.instances.insert(new_name.to_string(), extras);
+        Ok(())
+    }
+
+    /// Stores `extras` for a newly added instance.
+    pub fn add_extras(&mut self, name: &str, extras: InstanceExtras) -> Result<()> {
+        if self.instances.contains_key(name) {
+            bail!("an instance named '{}' already exists", name);
+        }
+        self.instances.insert(name.to_string(), extras);
+        Ok(())
+    }
+}
+
+/// Where the CLI config belonging to Sulphur's default config is kept.
+pub fn config_path() -> Result<PathBuf> {
+    Ok(xdg::BaseDirectories::with_prefix(XDG_PREFIX)?.place_config_file(CLI_CONFIG_FILE)?)
+}
+
+/// Sulphur's config directory.
+pub fn config_dir() -> Result<PathBuf> {
+    Ok(xdg::BaseDirectories::with_prefix(XDG_PREFIX)?.get_config_home())
+}
+
+/// Sulphur's data directory, where moved assets end up.
+pub fn data_dir() -> Result<PathBuf> {
+    Ok(xdg::BaseDirectories::with_prefix(XDG_PREFIX)?.get_data_home())
+}
+
+/// Where the CLI keeps the cache file called `file`.
+pub fn cache_path(file: &str) -> Result<PathBuf> {
+    Ok(xdg::BaseDirectories::with_prefix(XDG_PREFIX)?.place_cache_file(file)?)
+}
+
+/// Directory where files moved by the CLI are kept.
+pub fn storage_dir(kind: &str) -> Result<PathBuf> {
+    let dir = data_dir()?.join(kind);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
@@ -0,0 +1,422 @@
+use serde::{Deserialize, Serialize};
+use std::ffi::{OsStr, OsString};
+use std::io::Read;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime};
+use sulphur_core::Instance;
+
+use crate::asset_management::enabled_wads;
+use crate::cli_config::{cache_path, CliSettings, InstanceExtras};
+use crate::wad_utils::has_lump;
+
+/// Splits a user supplied command line into arguments, keeping the words
+/// as-is when the line isn't valid shell syntax.
+fn split_words(line: &str) -> Vec<OsString> {
+    shlex::split(line)
+        .unwrap_or_else(|| line.split_whitespace().map(str::to_string).collect())
+        .into_iter()
+        .map(OsString::from)
+        .collect()
+}
+
+/// The engine invocation for an instance: its override if it has one,
+/// otherwise the global GZDoom command.
+pub fn engine_argv(extras: &InstanceExtras, global_command: &OsStr) -> Vec<OsString> {
+    match &extras.engine {
+        Some(engine) => split_words(engine),
+        // The global command is usually a plain path, which may contain spaces.
+        None if Path::new(global_command).exists() => vec![global_command.to_os_string()],
+        None => split_words(&global_command.to_string_lossy()),
+    }
+}
+
+/// The exact argv used to launch an instance: environment and wrappers,
+/// the engine, then IWADs, mods, DeHackEd patches, save directory and
+/// additional parameters.
+pub fn get_full_command(
+    instance: &Instance,
+    extras: &InstanceExtras,
+    settings: &CliSettings,
+    global_command: &OsStr,
+) -> Vec<OsString> {
+    let mut argv: Vec<OsString> = vec![];
+
+    let mut env = settings.env.clone();
+    env.extend(extras.env.clone());
+    if !env.is_empty() {
+        argv.push("env".into());
+        argv.extend(
+            env.iter()
+                .map(|(key, value)| format!("{}={}", key, value).into()),
+        );
+    }
+    for wrapper in settings.wrappers.iter().chain(&extras.wrappers) {
+        argv.extend(split_words(wrapper));
+    }
+
+    argv.extend(engine_argv(extras, global_command));
+
+    for iwad in instance.gamedata.iwads.iter().filter(|iwad| iwad.0.enabled) {
+        argv.push("-iwad".into());
+        argv.push(iwad.0.path.clone().into());
+    }
+
+    let mut files: Vec<OsString> = instance
+        .gamedata
+        .mods
+        .iter()
+        .filter(|mod_| mod_.0.enabled)
+        .map(|mod_| mod_.0.path.clone().into())
+        .collect();
+
+    let mut dehs: Vec<OsString> = vec![];
+    let mut bexes: Vec<OsString> = vec![];
+    for patch in extras.patches.iter().filter(|patch| patch.0.enabled) {
+        let is_bex = patch
+            .0
+            .path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("bex"));
+        if settings.patches_as_file {
+            files.push(patch.0.path.clone().into());
+        } else if is_bex {
+            bexes.push(patch.0.path.clone().into());
+        } else {
+            dehs.push(patch.0.path.clone().into());
+        }
+    }
+    // Ports read the files following a single -deh or -bex and may not honour
+    // the parameter being repeated.
+    if !dehs.is_empty() {
+        argv.push("-deh".into());
+        argv.extend(dehs);
+    }
+    if !bexes.is_empty() {
+        argv.push("-bex".into());
+        argv.extend(bexes);
+    }
+
+    if !files.is_empty() {
+        argv.push("-file".into());
+        argv.extend(files);
+    }
+
+    if !instance.gamedata.savedir.as_os_str().is_empty() {
+        argv.push("-savedir".into());
+        argv.push(instance.gamedata.savedir.clone().into());
+    }
+
+    for param in &instance.gamedata.additional_params {
+        argv.extend(split_words(&param.to_string_lossy()));
+    }
+
+    argv
+}
+
+/// Quotes a single argument so that a POSIX shell reads it back unchanged.
+/// Bytes that aren't valid UTF-8 are produced with `printf` octal escapes.
+fn shell_quote(arg: &OsStr) -> String {
+    let bytes = arg.as_bytes();
+    let is_safe = |b: &u8| b.is_ascii_alphanumeric() || b"@%+=:,./_-".contains(b);
+    if !bytes.is_empty() && bytes.iter().all(is_safe) {
+        return arg.to_string_lossy().into_owned();
+    }
+
+    if bytes.is_empty() {
+        return "''".to_string();
+    }
+
+    let mut quoted = String::new();
+    for chunk in bytes.utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            quoted.push_str(&format!("'{}'", chunk.valid().replace('\'', r"'\''")));
+        }
+        if !chunk.invalid().is_empty() {
+            let escapes: String = chunk
+                .invalid()
+                .iter()
+                .map(|byte| format!("\\{:03o}", byte))
+                .collect();
+            quoted.push_str(&format!("\"$(printf '{}')\"", escapes));
+        }
+    }
+    quoted
+}
+
+pub fn to_shell_line(argv: &[OsString]) -> String {
+    argv.iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether some argument isn't valid UTF-8, so JSON can't hold it exactly.
+pub fn is_lossy(argv: &[OsString]) -> bool {
+    argv.iter().any(|arg| arg.to_str().is_none())
+}
+
+/// The arguments as a JSON array of strings. Invalid UTF-8 is replaced, see
+/// [`is_lossy`].
+pub fn to_json_array(argv: &[OsString]) -> String {
+    let items: Vec<String> = argv
+        .iter()
+        .map(|arg| {
+            let mut escaped = String::from("\"");
+            for c in arg.to_string_lossy().chars() {
+                match c {
+                    '"' => escaped.push_str("\\\""),
+                    '\\' => escaped.push_str("\\\\"),
+                    '\n' => escaped.push_str("\\n"),
+                    '\t' => escaped.push_str("\\t"),
+                    c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => escaped.push(c),
+                }
+            }
+            escaped.push('"');
+            escaped
+        })
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// A standalone script reproducing the launch outside of Sulphur.
+pub fn to_shell_script(argv: &[OsString]) -> String {
+    format!("#!/bin/sh\nexec {}\n", to_shell_line(argv))
+}
+
+/// Enabled WADs that already carry a `DEHACKED` lump, when the instance also
+/// loads separate patches.
+fn dehacked_conflicts(instance: &Instance, extras: &InstanceExtras) -> Vec<PathBuf> {
+    if !extras.patches.iter().any(|patch| patch.0.enabled) {
+        return vec![];
+    }
+
+    enabled_wads(instance)
+        .filter(|asset| has_lump(&asset.path, "DEHACKED"))
+        .map(|asset| asset.path.clone())
+        .collect()
+}
+
+pub fn print_dehacked_warnings(instance: &Instance, extras: &InstanceExtras) {
+    for path in dehacked_conflicts(instance, extras) {
+        println!(
+            "Warning: '{}' already contains a DEHACKED lump, loading extra patches may conflict with it.",
+            path.to_string_lossy()
+        );
+    }
+}
+
+const VERSION_CACHE_FILE: &str = "engine_versions.toml";
+
+/// Versions already probed, so engines are only run with `--version` again
+/// once their executable changed.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct VersionCache {
+    engines: Vec<CachedVersion>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedVersion {
+    path: PathBuf,
+    modified: SystemTime,
+    version: String,
+}
+
+/// The version of the engine, probed with `--version` only when its
+/// executable is new or was modified since it was last probed. Invocations
+/// with arguments, like `flatpak run ...`, don't name the engine's own file,
+/// so they are probed every time.
+pub fn engine_version(engine: &[OsString]) -> Option<String> {
+    let [program] = engine else {
+        return detect_engine_version(engine);
+    };
+    let Some((path, modified)) = find_program(program).and_then(|path| {
+        let modified = path.metadata().ok()?.modified().ok()?;
+        Some((path, modified))
+    }) else {
+        return detect_engine_version(engine);
+    };
+
+    let cache_file = cache_path(VERSION_CACHE_FILE).ok();
+    let mut cache: VersionCache = cache_file
+        .as_ref()
+        .and_then(|file| std::fs::read_to_string(file).ok())
+        .and_then(|text| toml::from_str(&text).ok())
+        .unwrap_or_default();
+    if let Some(cached) = cache
+        .engines
+        .iter()
+        .find(|cached| cached.path == path && cached.modified == modified)
+    {
+        return Some(cached.version.clone());
+    }
+
+    let version = detect_engine_version(engine)?;
+    cache.engines.retain(|cached| cached.path != path);
+    cache.engines.push(CachedVersion {
+        path,
+        modified,
+        version: version.clone(),
+    });
+    // The cache only saves time, failing to write it is not worth reporting.
+    if let (Some(file), Ok(text)) = (cache_file, toml::to_string(&cache)) {
+        let _ = std::fs::write(file, text);
+    }
+    Some(version)
+}
+
+/// The file a program name runs, looking it up in `PATH` unless it is a path.
+fn find_program(program: &OsStr) -> Option<PathBuf> {
+    let program = Path::new(program);
+    if program.components().count() > 1 {
+        return program.canonicalize().ok();
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+        .and_then(|path| path.canonicalize().ok())
+}
+
+/// First line printed by `<engine> --version`, if the engine could be run.
+/// Engines that ignore `--version` and start the game instead are killed after
+/// a few seconds.
+fn detect_engine_version(engine: &[OsString]) -> Option<String> {
+    const TIMEOUT: Duration = Duration::from_secs(5);
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    let (program, args) = engine.split_first()?;
+    let mut child = Command::new(program)
+        .args(args)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(POLL_INTERVAL),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+    if !status.success() {
+        return None;
+    }
+
+    let mut stdout = vec![];
+    child.stdout.take()?.read_to_end(&mut stdout).ok()?;
+    String::from_utf8_lossy(&stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_config::Patch;
+    use std::os::unix::ffi::OsStringExt;
+    use sulphur_core::{Asset, GameData, Metadata};
+
+    /// What `sh` makes of the quoted argument.
+    fn through_shell(quoted: &str) -> Vec<u8> {
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf '%s' {}", quoted))
+            .output()
+            .unwrap()
+            .stdout
+    }
+
+    #[test]
+    fn quotes_only_when_needed() {
+        assert_eq!(shell_quote("gzdoom".as_ref()), "gzdoom");
+        assert_eq!(shell_quote("/usr/bin/gzdoom".as_ref()), "/usr/bin/gzdoom");
+        assert_eq!(shell_quote("my wad.pk3".as_ref()), "'my wad.pk3'");
+        assert_eq!(shell_quote("it's".as_ref()), r"'it'\''s'");
+        assert_eq!(shell_quote("".as_ref()), "''");
+    }
+
+    #[test]
+    fn shell_reads_arguments_back_unchanged() {
+        let args: Vec<OsString> = vec![
+            "plain".into(),
+            "with space".into(),
+            "it's".into(),
+            "$HOME `x` \"y\" \\".into(),
+            "ünïcode".into(),
+            OsString::from_vec(b"bad\xff\xfe'utf8".to_vec()),
+            OsString::from_vec(vec![0x80]),
+        ];
+        for arg in args {
+            assert_eq!(through_shell(&shell_quote(&arg)), arg.as_bytes());
+        }
+    }
+
+    #[test]
+    fn shell_line_keeps_empty_arguments() {
+        let argv: Vec<OsString> = vec!["gzdoom".into(), "".into(), "a b".into()];
+        assert_eq!(to_shell_line(&argv), "gzdoom '' 'a b'");
+        assert_eq!(to_shell_script(&argv), "#!/bin/sh\nexec gzdoom '' 'a b'\n");
+    }
+
+    #[test]
+    fn json_is_lossy_only_for_invalid_utf8() {
+        let argv: Vec<OsString> = vec!["a \"b\"".into(), "c\\d".into()];
+        assert_eq!(to_json_array(&argv), r#"["a \"b\"", "c\\d"]"#);
+        assert!(!is_lossy(&argv));
+        assert!(is_lossy(&[OsString::from_vec(vec![0xff])]));
+    }
+
+    #[test]
+    fn patches_share_one_parameter_each() {
+        let instance = Instance {
+            metadata: Metadata {
+                name: "a".to_string(),
+                image: None,
+                playtime: Duration::ZERO,
+                last_played: None,
+                last_session_duration: None,
+            },
+            gamedata: GameData {
+                iwads: vec![],
+                mods: vec![],
+                savedir: Default::default(),
+                additional_params: vec![],
+            },
+        };
+        let patch = |path: &str, enabled: bool| {
+            Patch(Asset {
+                path: PathBuf::from(path),
+                enabled,
+            })
+        };
+        let extras = InstanceExtras {
+            patches: vec![
+                patch("b.deh", true),
+                patch("a.bex", true),
+                patch("off.deh", false),
+                patch("c.BEX", true),
+                patch("a.deh", true),
+            ],
+            ..Default::default()
+        };
+        let argv = get_full_command(&instance, &extras, &CliSettings::default(), "doom".as_ref());
+        let expected: Vec<OsString> = ["doom", "-deh", "b.deh", "a.deh", "-bex", "a.bex", "c.BEX"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(argv, expected);
+    }
+}
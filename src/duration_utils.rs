@@ -1,27 +1,347 @@
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
-pub trait ToString {
-    fn to_string(&self) -> String;
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// Units used when formatting, largest first: seconds per unit, compact
+/// suffix and verbose name.
+const UNITS: [(u64, &str, &str); 5] = [
+    (WEEK, "w", "week"),
+    (DAY, "d", "day"),
+    (HOUR, "h", "hour"),
+    (MINUTE, "m", "minute"),
+    (1, "s", "second"),
+];
+
+/// How durations are shown. Everything printed in any style can be read back
+/// with `parse_duration`.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum DurationStyle {
+    /// `1w 2d 3h 4m 5s`
+    #[default]
+    Compact,
+    /// `1 week, 2 days, 3 hours, 4 minutes, 5 seconds`
+    Verbose,
+    /// `P9DT3H4M5S`
+    Iso8601,
+}
+
+impl DurationStyle {
+    pub const ALL: [DurationStyle; 3] = [
+        DurationStyle::Compact,
+        DurationStyle::Verbose,
+        DurationStyle::Iso8601,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DurationStyle::Compact => "Compact (2h 30m)",
+            DurationStyle::Verbose => "Verbose (2 hours, 30 minutes)",
+            DurationStyle::Iso8601 => "ISO 8601 (PT2H30M)",
+        }
+    }
+
+    /// Formats a duration to whole seconds.
+    pub fn format(&self, duration: Duration) -> String {
+        match self {
+            DurationStyle::Compact => {
+                let parts = split_units(duration.as_secs());
+                if parts.is_empty() {
+                    return "0s".to_string();
+                }
+                parts
+                    .iter()
+                    .map(|(value, suffix, _)| format!("{}{}", value, suffix))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            DurationStyle::Verbose => {
+                let parts = split_units(duration.as_secs());
+                if parts.is_empty() {
+                    return "0 seconds".to_string();
+                }
+                parts
+                    .iter()
+                    .map(|(value, _, name)| {
+                        format!("{} {}{}", value, name, if *value == 1 { "" } else { "s" })
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+            DurationStyle::Iso8601 => {
+                // Weeks can't be combined with other units in ISO 8601, so
+                // they are written as days.
+                let seconds = duration.as_secs();
+                let days = seconds / DAY;
+                let time = [
+                    (seconds % DAY / HOUR, 'H'),
+                    (seconds % HOUR / MINUTE, 'M'),
+                    (seconds % MINUTE, 'S'),
+                ];
+
+                let mut text = String::from("P");
+                if days > 0 {
+                    text.push_str(&format!("{}D", days));
+                }
+                if time.iter().any(|&(value, _)| value > 0) || days == 0 {
+                    text.push('T');
+                    for (value, unit) in time {
+                        if value > 0 {
+                            text.push_str(&format!("{}{}", value, unit));
+                        }
+                    }
+                    if text == "PT" {
+                        text.push_str("0S");
+                    }
+                }
+                text
+            }
+        }
+    }
+}
+
+fn split_units(mut seconds: u64) -> Vec<(u64, &'static str, &'static str)> {
+    let mut parts = vec![];
+    for (unit, suffix, name) in UNITS {
+        let value = seconds / unit;
+        seconds %= unit;
+        if value > 0 {
+            parts.push((value, suffix, name));
+        }
+    }
+    parts
+}
+
+/// Parses a duration in any `DurationStyle`, e.g. `2h 30m`, `45m10s`,
+/// `1 day, 2 hours` or `PT2H30M`. A bare number means minutes.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(minutes) = text.parse::<u64>() {
+        return Some(Duration::from_secs(minutes.checked_mul(MINUTE)?));
+    }
+    if text.starts_with(['P', 'p']) {
+        return parse_iso8601(&text[1..]);
+    }
+
+    let mut seconds: u64 = 0;
+    let mut number: Option<u64> = None;
+    let mut any_unit = false;
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            if number.is_some() {
+                // Two numbers in a row without a unit in between.
+                return None;
+            }
+            let mut digits = String::new();
+            while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(digit);
+                chars.next();
+            }
+            number = Some(digits.parse().ok()?);
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(&letter) = chars.peek().filter(|c| c.is_alphabetic()) {
+                word.push(letter.to_ascii_lowercase());
+                chars.next();
+            }
+            if word == "and" && number.is_none() {
+                continue;
+            }
+            let unit = unit_seconds(&word)?;
+            seconds = seconds.checked_add(number.take()?.checked_mul(unit)?)?;
+            any_unit = true;
+        } else if c.is_whitespace() || c == ',' {
+            chars.next();
+        } else {
+            return None;
+        }
+    }
+
+    if number.is_some() || !any_unit {
+        return None;
+    }
+    Some(Duration::from_secs(seconds))
 }
 
-impl ToString for Duration {
-    fn to_string(&self) -> String {
-        let hours = self.as_secs() / 3600;
-        let minutes = (self.as_secs() % 3600) / 60;
-        let seconds = self.as_secs() % 60;
+fn unit_seconds(word: &str) -> Option<u64> {
+    Some(match word {
+        "w" | "wk" | "wks" | "week" | "weeks" => WEEK,
+        "d" | "day" | "days" => DAY,
+        "h" | "hr" | "hrs" | "hour" | "hours" => HOUR,
+        "m" | "min" | "mins" | "minute" | "minutes" => MINUTE,
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        _ => return None,
+    })
+}
 
-        let mut parts = Vec::new();
+/// Parses the part of an ISO 8601 duration after the leading `P`. Only
+/// weeks, days, hours, minutes and whole seconds are accepted.
+fn parse_iso8601(text: &str) -> Option<Duration> {
+    let mut seconds: u64 = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    let mut any_unit = false;
 
-        if hours > 0 {
-            parts.push(format!("{}h", hours));
+    for c in text.chars() {
+        match c.to_ascii_uppercase() {
+            '0'..='9' => number.push(c),
+            'T' if !in_time && number.is_empty() => in_time = true,
+            unit => {
+                let unit = match (in_time, unit) {
+                    (false, 'W') => WEEK,
+                    (false, 'D') => DAY,
+                    (true, 'H') => HOUR,
+                    (true, 'M') => MINUTE,
+                    (true, 'S') => 1,
+                    _ => return None,
+                };
+                let value: u64 = number.parse().ok()?;
+                number.clear();
+                seconds = seconds.checked_add(value.checked_mul(unit)?)?;
+                any_unit = true;
+            }
         }
-        if minutes > 0 {
-            parts.push(format!("{}m", minutes));
+    }
+
+    if !number.is_empty() || !any_unit {
+        return None;
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn seconds(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    /// Spellings the parser accepts for each unit, largest unit first.
+    const SPELLINGS: [(u64, &[&str]); 5] = [
+        (WEEK, &["w", "wk", "wks", "week", "weeks"]),
+        (DAY, &["d", "day", "days"]),
+        (HOUR, &["h", "hr", "hrs", "hour", "hours"]),
+        (MINUTE, &["m", "min", "mins", "minute", "minutes"]),
+        (1, &["s", "sec", "secs", "second", "seconds"]),
+    ];
+
+    /// A duration written out by hand: units in any order, any spelling and
+    /// case, with or without spaces, commas and "and" between them. Gives the
+    /// text and the seconds it stands for.
+    fn written_duration() -> impl Strategy<Value = (String, u64)> {
+        let part = (0..10_000u64, 0..5usize, any::<bool>(), any::<bool>());
+        let separators = prop::sample::select(vec!["", " ", "  ", ", ", ",", " and ", "\t"]);
+        (
+            Just((0..SPELLINGS.len()).collect::<Vec<_>>()).prop_shuffle(),
+            1..=SPELLINGS.len(),
+            prop::collection::vec((part, separators), SPELLINGS.len()),
+            "[ \t]{0,3}",
+            "[ \t]{0,3}",
+        )
+            .prop_map(|(order, count, parts, leading, trailing)| {
+                let mut text = leading;
+                let mut seconds = 0;
+                for (position, &unit) in order.iter().take(count).enumerate() {
+                    let ((value, spelling, upper, spaced), separator) = parts[unit];
+                    let (unit_seconds, spellings) = SPELLINGS[unit];
+                    if position > 0 {
+                        text.push_str(separator);
+                    }
+                    let word = spellings[spelling % spellings.len()];
+                    text.push_str(&value.to_string());
+                    if spaced {
+                        text.push(' ');
+                    }
+                    if upper {
+                        text.push_str(&word.to_uppercase());
+                    } else {
+                        text.push_str(word);
+                    }
+                    seconds += value * unit_seconds;
+                }
+                text.push_str(&trailing);
+                (text, seconds)
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn every_style_round_trips(duration in prop_oneof![0..=2 * WEEK, any::<u64>()]) {
+            for style in DurationStyle::ALL {
+                let text = style.format(seconds(duration));
+                prop_assert_eq!(parse_duration(&text), Some(seconds(duration)), "{}", text);
+            }
         }
-        if seconds > 0 || parts.is_empty() {
-            parts.push(format!("{}s", seconds));
+
+        #[test]
+        fn written_durations_parse((text, duration) in written_duration()) {
+            prop_assert_eq!(parse_duration(&text), Some(seconds(duration)));
         }
+    }
+
+    #[test]
+    fn formats() {
+        let duration = seconds(WEEK + 2 * DAY + 3 * HOUR + 4 * MINUTE + 5);
+        assert_eq!(DurationStyle::Compact.format(duration), "1w 2d 3h 4m 5s");
+        assert_eq!(
+            DurationStyle::Verbose.format(duration),
+            "1 week, 2 days, 3 hours, 4 minutes, 5 seconds"
+        );
+        assert_eq!(DurationStyle::Iso8601.format(duration), "P9DT3H4M5S");
+        assert_eq!(DurationStyle::Iso8601.format(seconds(DAY)), "P1D");
+        assert_eq!(DurationStyle::Iso8601.format(seconds(0)), "PT0S");
+        assert_eq!(DurationStyle::Verbose.format(seconds(0)), "0 seconds");
+    }
+
+    #[test]
+    fn bare_number_means_minutes() {
+        assert_eq!(parse_duration("90"), Some(seconds(90 * MINUTE)));
+        assert_eq!(parse_duration(" 0 "), Some(seconds(0)));
+    }
+
+    #[test]
+    fn words_and_case() {
+        assert_eq!(
+            parse_duration("1 hour and 30 minutes"),
+            Some(seconds(HOUR + 30 * MINUTE))
+        );
+        assert_eq!(
+            parse_duration("2H 30M"),
+            Some(seconds(2 * HOUR + 30 * MINUTE))
+        );
+        assert_eq!(
+            parse_duration("1 Day, 2 HRS"),
+            Some(seconds(DAY + 2 * HOUR))
+        );
+        assert_eq!(parse_duration("45m10s"), Some(seconds(45 * MINUTE + 10)));
+    }
 
-        parts.join(" ")
+    #[test]
+    fn iso8601() {
+        assert_eq!(parse_duration("PT0S"), Some(seconds(0)));
+        assert_eq!(
+            parse_duration("pt2h30m"),
+            Some(seconds(2 * HOUR + 30 * MINUTE))
+        );
+        assert_eq!(parse_duration("P1W"), Some(seconds(WEEK)));
+        assert_eq!(parse_duration("P1H"), None);
+        assert_eq!(parse_duration("PT"), None);
+        assert_eq!(parse_duration("PT5"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for text in ["", "m", "5 5m", "3x", "1h 30", "-5m", "and"] {
+            assert_eq!(parse_duration(text), None, "'{}'", text);
+        }
     }
 }
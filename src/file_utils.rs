@@ -1,7 +1,11 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use console::Style;
-use dialoguer::FuzzySelect;
-use std::path::PathBuf;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::input::Select;
 
 pub fn select_file(
     prompt: &str,
@@ -101,7 +105,7 @@ pub fn select_file(
 
         let display_items: Vec<&str> = entries.iter().map(|(name, _, _)| name.as_str()).collect();
 
-        let selection = FuzzySelect::new()
+        let selection = Select::fuzzy()
             .with_prompt(prompt)
             .items(&display_items)
             .interact()?;
@@ -122,3 +126,184 @@ pub fn select_file(
         }
     }
 }
+
+/// Moves `file` into `dir`, falling back to copy + delete across filesystems.
+/// Files already in `dir` are never overwritten, the moved file is renamed instead.
+pub fn move_into(file: &Path, dir: &Path) -> Result<PathBuf> {
+    let Some(file_name) = file.file_name() else {
+        bail!("cannot move '{}'", file.to_string_lossy());
+    };
+
+    // Pick a name that isn't taken yet, keeping the extension engines look at.
+    let mut destination = dir.join(file_name);
+    let mut counter = 1;
+    while destination.exists() {
+        counter += 1;
+        let mut name = file.file_stem().unwrap_or(file_name).to_os_string();
+        name.push(format!(".{}", counter));
+        if let Some(extension) = file.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        destination = dir.join(name);
+    }
+
+    if std::fs::rename(file, &destination).is_err() {
+        std::fs::copy(file, &destination)?;
+        std::fs::remove_file(file)?;
+    }
+    Ok(destination)
+}
+
+/// Turns an instance name into something usable as a file name, replacing path
+/// separators and control characters so it can't point outside the directory.
+pub fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_start_matches('.');
+    if name.is_empty() {
+        "instance".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Copies `text` using whichever clipboard tool is available.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    const CLIPBOARD_TOOLS: &[&[&str]] = &[
+        &["wl-copy"],
+        &["xclip", "-selection", "clipboard"],
+        &["xsel", "--clipboard", "--input"],
+        &["pbcopy"],
+    ];
+
+    for tool in CLIPBOARD_TOOLS {
+        let Ok(mut child) = Command::new(tool[0])
+            .args(&tool[1..])
+            .stdin(Stdio::piped())
+            .spawn()
+        else {
+            continue;
+        };
+        child.stdin.take().unwrap().write_all(text.as_bytes())?;
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    bail!("no clipboard tool found (tried wl-copy, xclip, xsel and pbcopy)")
+}
+
+/// Recursively copies the contents of `source` into `destination`.
+pub fn copy_dir_all(source: &Path, destination: &Path) -> Result<()> {
+    std::fs::create_dir_all(destination)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds a text file to an existing zip archive.
+pub fn add_text_to_zip(archive: &Path, name: &str, text: &str) -> Result<()> {
+    let file = OpenOptions::new().read(true).write(true).open(archive)?;
+    let mut writer = zip::ZipWriter::new_append(file)?;
+    writer.start_file(name, zip::write::FileOptions::default())?;
+    writer.write_all(text.as_bytes())?;
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn read_text_from_zip(archive: &Path, name: &str) -> Option<String> {
+    let mut archive = zip::ZipArchive::new(File::open(archive).ok()?).ok()?;
+    let mut text = String::new();
+    archive.by_name(name).ok()?.read_to_string(&mut text).ok()?;
+    Some(text)
+}
+
+/// Moves `path` (a file or directory) to the user's trash instead of deleting
+/// it: `~/.Trash` on macOS, the freedesktop.org home trash elsewhere.
+pub fn move_to_trash(path: &Path) -> Result<()> {
+    let path = std::fs::canonicalize(path)?;
+    let Some(file_name) = path.file_name() else {
+        bail!("cannot trash '{}'", path.to_string_lossy());
+    };
+
+    let (files_dir, info_dir) = if cfg!(target_os = "macos") {
+        let Some(home) = std::env::home_dir() else {
+            bail!("no home directory to find the trash in");
+        };
+        (home.join(".Trash"), None)
+    } else {
+        let trash = xdg::BaseDirectories::new()?.get_data_home().join("Trash");
+        (trash.join("files"), Some(trash.join("info")))
+    };
+    std::fs::create_dir_all(&files_dir)?;
+
+    // Pick a name that isn't taken in the trash yet.
+    let mut name = file_name.to_os_string();
+    let mut counter = 1;
+    while files_dir.join(&name).exists()
+        || info_dir
+            .as_ref()
+            .is_some_and(|info_dir| info_dir.join(trash_info_name(&name)).exists())
+    {
+        counter += 1;
+        name = file_name.to_os_string();
+        name.push(format!(".{}", counter));
+    }
+
+    if let Some(info_dir) = &info_dir {
+        std::fs::create_dir_all(info_dir)?;
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            percent_encode_path(&path),
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+        std::fs::write(info_dir.join(trash_info_name(&name)), info)?;
+    }
+
+    let destination = files_dir.join(&name);
+    if std::fs::rename(&path, &destination).is_err() {
+        // Different filesystem: copy into the trash, then remove the original.
+        if path.is_dir() {
+            copy_dir_all(&path, &destination)?;
+            std::fs::remove_dir_all(&path)?;
+        } else {
+            std::fs::copy(&path, &destination)?;
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+fn trash_info_name(name: &std::ffi::OsStr) -> std::ffi::OsString {
+    let mut info_name = name.to_os_string();
+    info_name.push(".trashinfo");
+    info_name
+}
+
+/// Encodes a path the way `.trashinfo` files expect, as in a `file://` URL.
+fn percent_encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
@@ -0,0 +1,451 @@
+use anyhow::{bail, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use console::Term;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::wad_utils::read_lump;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Largest image decoded, so that a bogus header can't exhaust memory.
+const MAX_PIXELS: usize = 4096 * 4096;
+const MAX_COLUMNS: usize = 60;
+/// Rough width of a terminal cell in pixels, used to size sixel output.
+const CELL_WIDTH: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum ImageProtocol {
+    #[default]
+    Auto,
+    Kitty,
+    Iterm,
+    Sixel,
+    Blocks,
+}
+
+impl ImageProtocol {
+    pub const ALL: [ImageProtocol; 5] = [
+        ImageProtocol::Auto,
+        ImageProtocol::Kitty,
+        ImageProtocol::Iterm,
+        ImageProtocol::Sixel,
+        ImageProtocol::Blocks,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageProtocol::Auto => "Detect automatically",
+            ImageProtocol::Kitty => "Kitty graphics protocol",
+            ImageProtocol::Iterm => "iTerm inline images",
+            ImageProtocol::Sixel => "Sixel",
+            ImageProtocol::Blocks => "Coloured half-blocks",
+        }
+    }
+
+    fn detect() -> Self {
+        let env = |key: &str| std::env::var(key).unwrap_or_default();
+        let term = env("TERM");
+        let program = env("TERM_PROGRAM");
+
+        if !env("KITTY_WINDOW_ID").is_empty() || term.contains("kitty") || program == "ghostty" {
+            ImageProtocol::Kitty
+        } else if program == "iTerm.app" || program == "WezTerm" {
+            ImageProtocol::Iterm
+        } else if ["sixel", "foot", "mlterm", "contour"]
+            .iter()
+            .any(|name| term.contains(name))
+        {
+            ImageProtocol::Sixel
+        } else {
+            ImageProtocol::Blocks
+        }
+    }
+}
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    /// RGBA pixels, row by row.
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Nearest-neighbour resize to the given width, keeping the aspect ratio.
+    fn fit_width(&self, width: usize) -> Image {
+        let width = width.clamp(1, self.width.max(1));
+        let height = (self.height * width / self.width.max(1)).max(1);
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.pixel(x * self.width / width, y * self.height / height))
+            .collect();
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+/// Checks that an image of the given size can be decoded.
+fn check_size(width: usize, height: usize) -> Result<()> {
+    if width == 0 || height == 0 {
+        bail!("image has no pixels");
+    }
+    if width.saturating_mul(height) > MAX_PIXELS {
+        bail!("image is too large ({}x{})", width, height);
+    }
+    Ok(())
+}
+
+/// Decodes a PNG image of any colour type into RGBA pixels.
+pub fn decode_png(data: &[u8]) -> Result<Image> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        bail!("not a PNG image");
+    }
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let (width, height) = reader.info().size();
+    check_size(width as usize, height as usize)?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer)?;
+    let bytes = &buffer[..frame.buffer_size()];
+    let pixels = match frame.color_type {
+        png::ColorType::Grayscale => bytes.iter().map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::GrayscaleAlpha => bytes
+            .chunks_exact(2)
+            .map(|pixel| [pixel[0], pixel[0], pixel[0], pixel[1]])
+            .collect(),
+        png::ColorType::Rgb => bytes
+            .chunks_exact(3)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect(),
+        png::ColorType::Rgba => bytes
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+            .collect(),
+        // Palettes are expanded by the transformations above.
+        png::ColorType::Indexed => bail!("unexpected indexed PNG output"),
+    };
+
+    Ok(Image {
+        width: frame.width as usize,
+        height: frame.height as usize,
+        pixels,
+    })
+}
+
+pub fn encode_png(image: &Image) -> Result<Vec<u8>> {
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.pixels.as_flattened())?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// Decodes a picture in the Doom patch format, or a raw fullscreen 320x200 one.
+fn decode_doom_picture(data: &[u8], palette: &[u8]) -> Result<Image> {
+    const RAW_FULLSCREEN: (usize, usize) = (320, 200);
+
+    let color = |index: u8| {
+        let index = index as usize * 3;
+        [palette[index], palette[index + 1], palette[index + 2], 255]
+    };
+
+    if data.len() == RAW_FULLSCREEN.0 * RAW_FULLSCREEN.1 {
+        return Ok(Image {
+            width: RAW_FULLSCREEN.0,
+            height: RAW_FULLSCREEN.1,
+            pixels: data.iter().map(|&index| color(index)).collect(),
+        });
+    }
+
+    if data.len() < 8 {
+        bail!("picture is too small");
+    }
+    let width = u16::from_le_bytes([data[0], data[1]]) as usize;
+    let height = u16::from_le_bytes([data[2], data[3]]) as usize;
+    check_size(width, height)?;
+    if data.len() < 8 + width * 4 {
+        bail!("picture is truncated");
+    }
+
+    let mut pixels = vec![[0, 0, 0, 0]; width * height];
+    for x in 0..width {
+        let offset = 8 + x * 4;
+        let mut position =
+            u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        loop {
+            let Some(&top) = data.get(position) else {
+                bail!("picture column {} is truncated", x);
+            };
+            if top == 0xFF {
+                break;
+            }
+            let length = *data.get(position + 1).unwrap_or(&0) as usize;
+            let Some(post) = data.get(position + 3..position + 3 + length) else {
+                bail!("picture column {} is truncated", x);
+            };
+            for (i, &index) in post.iter().enumerate() {
+                let y = top as usize + i;
+                if y < height {
+                    pixels[y * width + x] = color(index);
+                }
+            }
+            position += length + 4;
+        }
+    }
+
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Finds the `TITLEPIC` the engine would show for the given WADs (in load
+/// order), coloured with the last `PLAYPAL` among them.
+pub fn extract_titlepic(wads: &[PathBuf]) -> Result<Option<Image>> {
+    let Some(picture) = wads
+        .iter()
+        .rev()
+        .find_map(|wad| read_lump(wad, "TITLEPIC").ok().flatten())
+    else {
+        return Ok(None);
+    };
+    if picture.starts_with(PNG_SIGNATURE) {
+        return decode_png(&picture).map(Some);
+    }
+
+    let Some(palette) = wads
+        .iter()
+        .rev()
+        .find_map(|wad| read_lump(wad, "PLAYPAL").ok().flatten())
+        .filter(|palette| palette.len() >= 768)
+    else {
+        bail!("no PLAYPAL lump found to colour the TITLEPIC with");
+    };
+    decode_doom_picture(&picture, &palette).map(Some)
+}
+
+fn print_kitty(png: &[u8], columns: usize) {
+    const CHUNK_SIZE: usize = 4096;
+
+    let encoded = BASE64.encode(png);
+    let chunks: Vec<&str> = encoded
+        .as_bytes()
+        .chunks(CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap())
+        .collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            print!("\x1b_Gf=100,a=T,c={},m={};{}\x1b\\", columns, more, chunk);
+        } else {
+            print!("\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+    println!();
+}
+
+fn print_iterm(data: &[u8], columns: usize) {
+    println!(
+        "\x1b]1337;File=inline=1;size={};width={};preserveAspectRatio=1:{}\x07",
+        data.len(),
+        columns,
+        BASE64.encode(data)
+    );
+}
+
+fn print_sixel(image: &Image) {
+    const LEVELS: usize = 6;
+
+    let color_index = |pixel: [u8; 4]| -> Option<usize> {
+        if pixel[3] < 128 {
+            return None;
+        }
+        let level = |value: u8| (value as usize * (LEVELS - 1) + 127) / 255;
+        Some(level(pixel[0]) * LEVELS * LEVELS + level(pixel[1]) * LEVELS + level(pixel[2]))
+    };
+
+    // P2=1 leaves transparent pixels untouched.
+    let mut output = format!("\x1bP0;1q\"1;1;{};{}", image.width, image.height);
+    for index in 0..LEVELS * LEVELS * LEVELS {
+        let percent = |level: usize| level * 100 / (LEVELS - 1);
+        output.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            percent(index / (LEVELS * LEVELS)),
+            percent(index / LEVELS % LEVELS),
+            percent(index % LEVELS)
+        ));
+    }
+
+    for band in (0..image.height).step_by(6) {
+        let band_height = (image.height - band).min(6);
+        let indices: Vec<Vec<Option<usize>>> = (0..band_height)
+            .map(|dy| {
+                (0..image.width)
+                    .map(|x| color_index(image.pixel(x, band + dy)))
+                    .collect()
+            })
+            .collect();
+        let mut colors: Vec<usize> = indices.iter().flatten().flatten().copied().collect();
+        colors.sort_unstable();
+        colors.dedup();
+
+        for color in colors {
+            output.push_str(&format!("#{}", color));
+            let sixels: Vec<char> = (0..image.width)
+                .map(|x| {
+                    let bits = (0..band_height)
+                        .filter(|&dy| indices[dy][x] == Some(color))
+                        .fold(0u8, |bits, dy| bits | 1 << dy);
+                    (63 + bits) as char
+                })
+                .collect();
+            for run in sixels.chunk_by(|a, b| a == b) {
+                if run.len() > 3 {
+                    output.push_str(&format!("!{}{}", run.len(), run[0]));
+                } else {
+                    output.extend(run);
+                }
+            }
+            output.push('$');
+        }
+        output.push('-');
+    }
+    output.push_str("\x1b\\");
+    println!("{}", output);
+}
+
+fn print_half_blocks(image: &Image) {
+    let is_visible = |pixel: [u8; 4]| pixel[3] >= 128;
+    for y in (0..image.height).step_by(2) {
+        let mut line = String::new();
+        for x in 0..image.width {
+            let top = image.pixel(x, y);
+            let bottom = if y + 1 < image.height {
+                image.pixel(x, y + 1)
+            } else {
+                [0, 0, 0, 0]
+            };
+            line.push_str(&match (is_visible(top), is_visible(bottom)) {
+                (true, true) => format!(
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m▀",
+                    top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+                ),
+                (true, false) => format!("\x1b[49;38;2;{};{};{}m▀", top[0], top[1], top[2]),
+                (false, true) => {
+                    format!("\x1b[49;38;2;{};{};{}m▄", bottom[0], bottom[1], bottom[2])
+                }
+                (false, false) => "\x1b[0m ".to_string(),
+            });
+        }
+        println!("{}\x1b[0m", line);
+    }
+}
+
+/// Shows an image file in the terminal, using the given protocol or the best
+/// one the terminal seems to support.
+pub fn display_image(path: &Path, protocol: ImageProtocol) -> Result<()> {
+    let data = std::fs::read(path)?;
+    let columns = (Term::stdout().size().1 as usize).clamp(1, MAX_COLUMNS);
+
+    let protocol = match protocol {
+        ImageProtocol::Auto => ImageProtocol::detect(),
+        protocol => protocol,
+    };
+    match protocol {
+        ImageProtocol::Iterm => print_iterm(&data, columns),
+        ImageProtocol::Kitty => {
+            if !data.starts_with(PNG_SIGNATURE) {
+                bail!("only PNG images can be shown with the kitty graphics protocol");
+            }
+            print_kitty(&data, columns)
+        }
+        ImageProtocol::Sixel => print_sixel(&decode_png(&data)?.fit_width(columns * CELL_WIDTH)),
+        ImageProtocol::Blocks | ImageProtocol::Auto => {
+            print_half_blocks(&decode_png(&data)?.fit_width(columns))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PNG with only its header, claiming the given size.
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        drop(encoder.write_header().unwrap());
+        png
+    }
+
+    #[test]
+    fn png_round_trips() {
+        let image = Image {
+            width: 2,
+            height: 2,
+            pixels: vec![
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+                [0, 0, 255, 128],
+                [9, 9, 9, 0],
+            ],
+        };
+        let decoded = decode_png(&encode_png(&image).unwrap()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (2, 2));
+        assert_eq!(decoded.pixels, image.pixels);
+    }
+
+    #[test]
+    fn expands_grey_images() {
+        let mut png = vec![];
+        let mut encoder = png::Encoder::new(&mut png, 2, 1);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[10, 255, 200, 0]).unwrap();
+        writer.finish().unwrap();
+
+        let decoded = decode_png(&png).unwrap();
+        assert_eq!(decoded.pixels, vec![[10, 10, 10, 255], [200, 200, 200, 0]]);
+    }
+
+    #[test]
+    fn rejects_malformed_pngs() {
+        let image = Image {
+            width: 1,
+            height: 1,
+            pixels: vec![[0, 0, 0, 255]],
+        };
+        let png = encode_png(&image).unwrap();
+        assert!(decode_png(&png[..png.len() / 2]).is_err());
+        assert!(decode_png(&png[1..]).is_err());
+        assert!(decode_png(&png_header(u32::MAX / 2, u32::MAX / 2)).is_err());
+        assert!(decode_png(&png_header(5000, 5000)).is_err());
+    }
+
+    #[test]
+    fn rejects_doom_pictures_without_pixels_or_too_large() {
+        let palette = [0; 768];
+        for (width, height) in [(0, 10), (10, 0), (u16::MAX, u16::MAX)] {
+            let mut data = vec![];
+            data.extend_from_slice(&width.to_le_bytes());
+            data.extend_from_slice(&height.to_le_bytes());
+            data.extend_from_slice(&[0; 4]);
+            assert!(decode_doom_picture(&data, &palette).is_err());
+        }
+    }
+}
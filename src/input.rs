@@ -0,0 +1,382 @@
+//! Every prompt goes through the installed `InputProvider`, so that menus can
+//! be answered from a script as well as from the terminal. The builders mirror
+//! the dialoguer ones they replace.
+
+use anyhow::{anyhow, bail, Result};
+use dialoguer::theme::ColorfulTheme;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Check applied to text input; the error is shown to the user.
+pub type Validator<'a> = dyn Fn(&str) -> Result<(), String> + 'a;
+
+pub trait InputProvider: Send {
+    fn select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        default: usize,
+        fuzzy: bool,
+    ) -> Result<usize>;
+    /// Without a default an explicit yes or no is required.
+    fn confirm(&mut self, prompt: &str, default: Option<bool>) -> Result<bool>;
+    fn input(
+        &mut self,
+        prompt: &str,
+        default: Option<&str>,
+        allow_empty: bool,
+        validate: &Validator,
+    ) -> Result<String>;
+    /// Edits `text` as a whole, returning `None` if the edit was abandoned.
+    fn edit(&mut self, text: &str) -> Result<Option<String>>;
+}
+
+static PROVIDER: Mutex<Option<Box<dyn InputProvider>>> = Mutex::new(None);
+
+/// Replaces the provider used by every prompt. The terminal is used until
+/// one is installed.
+pub fn install(provider: Box<dyn InputProvider>) {
+    *PROVIDER.lock().unwrap() = Some(provider);
+}
+
+fn with_provider<R>(prompt: impl FnOnce(&mut dyn InputProvider) -> Result<R>) -> Result<R> {
+    let mut provider = PROVIDER.lock().unwrap();
+    prompt(
+        provider
+            .get_or_insert_with(|| Box::new(TerminalInput))
+            .as_mut(),
+    )
+}
+
+/// Prompts on the terminal through dialoguer.
+pub struct TerminalInput;
+
+impl InputProvider for TerminalInput {
+    fn select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        default: usize,
+        fuzzy: bool,
+    ) -> Result<usize> {
+        let theme = ColorfulTheme::default();
+        Ok(if fuzzy {
+            dialoguer::FuzzySelect::with_theme(&theme)
+                .with_prompt(prompt)
+                .items(items)
+                .default(default)
+                .interact()?
+        } else {
+            dialoguer::Select::with_theme(&theme)
+                .with_prompt(prompt)
+                .items(items)
+                .default(default)
+                .interact()?
+        })
+    }
+
+    fn confirm(&mut self, prompt: &str, default: Option<bool>) -> Result<bool> {
+        let theme = ColorfulTheme::default();
+        let mut confirm = dialoguer::Confirm::with_theme(&theme).with_prompt(prompt);
+        if let Some(default) = default {
+            confirm = confirm.default(default);
+        }
+        Ok(confirm.interact()?)
+    }
+
+    fn input(
+        &mut self,
+        prompt: &str,
+        default: Option<&str>,
+        allow_empty: bool,
+        validate: &Validator,
+    ) -> Result<String> {
+        let theme = ColorfulTheme::default();
+        let mut input = dialoguer::Input::<String>::with_theme(&theme)
+            .with_prompt(prompt)
+            .allow_empty(allow_empty)
+            .validate_with(|text: &String| validate(text));
+        if let Some(default) = default {
+            input = input.default(default.to_string());
+        }
+        Ok(input.interact_text()?)
+    }
+
+    fn edit(&mut self, text: &str) -> Result<Option<String>> {
+        Ok(dialoguer::Editor::new().edit(text)?)
+    }
+}
+
+/// Answers prompts from a script, one line per answer:
+///
+/// - selections take the item text (a unique prefix is enough) or `@N` for
+///   the N-th item,
+/// - confirmations take `y`/`yes` or `n`/`no`,
+/// - text input is taken as-is,
+/// - edited text spans several lines and ends with a line holding only `.`.
+///
+/// An empty line accepts the default. Lines starting with `;` are comments.
+/// Each prompt is echoed with its answer so runs can be followed in logs.
+pub struct ScriptedInput {
+    lines: Box<dyn BufRead + Send>,
+}
+
+impl ScriptedInput {
+    pub fn new(reader: impl BufRead + Send + 'static) -> Self {
+        Self {
+            lines: Box::new(reader),
+        }
+    }
+
+    /// Reads the script from a file, or from stdin for `-`.
+    pub fn open(path: &Path) -> Result<Self> {
+        if path.as_os_str() == "-" {
+            Ok(Self::new(BufReader::new(std::io::stdin())))
+        } else {
+            Ok(Self::new(BufReader::new(std::fs::File::open(path)?)))
+        }
+    }
+
+    fn next_line(&mut self, prompt: &str) -> Result<String> {
+        loop {
+            let mut line = String::new();
+            if self.lines.read_line(&mut line)? == 0 {
+                bail!("input script ended while waiting for '{}'", prompt);
+            }
+            let line = line.trim_end_matches(['\n', '\r']);
+            if !line.starts_with(';') {
+                return Ok(line.to_string());
+            }
+        }
+    }
+}
+
+impl InputProvider for ScriptedInput {
+    fn select(
+        &mut self,
+        prompt: &str,
+        items: &[String],
+        default: usize,
+        _fuzzy: bool,
+    ) -> Result<usize> {
+        let answer = self.next_line(prompt)?;
+        let plain: Vec<String> = items
+            .iter()
+            .map(|item| console::strip_ansi_codes(item).trim().to_lowercase())
+            .collect();
+        let wanted = answer.trim().to_lowercase();
+
+        let index = if wanted.is_empty() {
+            Some(default)
+        } else if let Some(number) = wanted.strip_prefix('@') {
+            number
+                .parse::<usize>()
+                .ok()
+                .and_then(|number| number.checked_sub(1))
+        } else if let Some(index) = plain.iter().position(|item| *item == wanted) {
+            Some(index)
+        } else {
+            let mut matches = plain
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| item.starts_with(&wanted));
+            match (matches.next(), matches.next()) {
+                (Some((index, _)), None) => Some(index),
+                _ => None,
+            }
+        };
+
+        let index = index
+            .filter(|&index| index < items.len())
+            .ok_or_else(|| anyhow!("'{}' is not one of the choices for '{}'", answer, prompt))?;
+        println!("{} › {}", prompt, plain[index]);
+        Ok(index)
+    }
+
+    fn confirm(&mut self, prompt: &str, default: Option<bool>) -> Result<bool> {
+        let answer = self.next_line(prompt)?;
+        let value = match (answer.trim().to_lowercase().as_str(), default) {
+            ("", Some(default)) => default,
+            ("y" | "yes", _) => true,
+            ("n" | "no", _) => false,
+            _ => bail!("'{}' is not a yes/no answer for '{}'", answer, prompt),
+        };
+        println!("{} › {}", prompt, if value { "yes" } else { "no" });
+        Ok(value)
+    }
+
+    fn input(
+        &mut self,
+        prompt: &str,
+        default: Option<&str>,
+        allow_empty: bool,
+        validate: &Validator,
+    ) -> Result<String> {
+        let mut answer = self.next_line(prompt)?;
+        if answer.is_empty() {
+            answer = default.unwrap_or_default().to_string();
+        }
+        if answer.is_empty() && !allow_empty {
+            bail!("'{}' needs an answer", prompt);
+        }
+        if let Err(error) = validate(&answer) {
+            bail!("'{}' is not valid for '{}': {}", answer, prompt, error);
+        }
+        println!("{} › {}", prompt, answer);
+        Ok(answer)
+    }
+
+    fn edit(&mut self, _text: &str) -> Result<Option<String>> {
+        let mut lines = vec![];
+        loop {
+            let line = self.next_line("edited text")?;
+            if line == "." {
+                break;
+            }
+            lines.push(line);
+        }
+        Ok(Some(lines.join("\n")))
+    }
+}
+
+#[derive(Default)]
+pub struct Select {
+    prompt: String,
+    items: Vec<String>,
+    default: usize,
+    fuzzy: bool,
+}
+
+impl Select {
+    pub fn new() -> Self {
+        <Self as Default>::default()
+    }
+
+    /// A selection that can be narrowed down by typing.
+    pub fn fuzzy() -> Self {
+        Self {
+            fuzzy: true,
+            ..Self::new()
+        }
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    pub fn items<T: ToString>(mut self, items: &[T]) -> Self {
+        self.items = items.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    pub fn default(mut self, default: usize) -> Self {
+        self.default = default;
+        self
+    }
+
+    pub fn interact(self) -> Result<usize> {
+        with_provider(|provider| {
+            provider.select(&self.prompt, &self.items, self.default, self.fuzzy)
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct Confirm {
+    prompt: String,
+    default: Option<bool>,
+}
+
+impl Confirm {
+    pub fn new() -> Self {
+        <Self as Default>::default()
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    pub fn default(mut self, default: bool) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    pub fn interact(self) -> Result<bool> {
+        with_provider(|provider| provider.confirm(&self.prompt, self.default))
+    }
+}
+
+pub struct Input<'a> {
+    prompt: String,
+    default: Option<String>,
+    allow_empty: bool,
+    validator: Box<Validator<'a>>,
+}
+
+impl Default for Input<'_> {
+    fn default() -> Self {
+        Self {
+            prompt: String::new(),
+            default: None,
+            allow_empty: false,
+            validator: Box::new(|_| Ok(())),
+        }
+    }
+}
+
+impl<'a> Input<'a> {
+    pub fn new() -> Self {
+        <Self as Default>::default()
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = prompt.into();
+        self
+    }
+
+    pub fn default(mut self, default: String) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    pub fn allow_empty(mut self, allow_empty: bool) -> Self {
+        self.allow_empty = allow_empty;
+        self
+    }
+
+    pub fn validate_with<E: ToString>(
+        mut self,
+        validator: impl Fn(&String) -> Result<(), E> + 'a,
+    ) -> Self {
+        self.validator =
+            Box::new(move |text| validator(&text.to_string()).map_err(|error| error.to_string()));
+        self
+    }
+
+    pub fn interact_text(self) -> Result<String> {
+        with_provider(|provider| {
+            provider.input(
+                &self.prompt,
+                self.default.as_deref(),
+                self.allow_empty,
+                self.validator.as_ref(),
+            )
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct Editor;
+
+impl Editor {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn edit(&self, text: &str) -> Result<Option<String>> {
+        with_provider(|provider| provider.edit(text))
+    }
+}
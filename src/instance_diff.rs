@@ -0,0 +1,277 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use sulphur_core::{Asset, GameData, Instance};
+
+use crate::cli_config::InstanceExtras;
+
+fn file_name(asset: &Asset) -> String {
+    asset
+        .path
+        .file_name()
+        .unwrap_or(asset.path.as_os_str())
+        .to_string_lossy()
+        .to_string()
+}
+
+fn asset_changes(kind: &str, old: &[Asset], new: &[Asset]) -> Vec<String> {
+    let mut changes = vec![];
+
+    for asset in old {
+        if !new.iter().any(|other| other.path == asset.path) {
+            changes.push(format!("Removed {} {}", kind, file_name(asset)));
+        }
+    }
+    for asset in new {
+        match old.iter().find(|other| other.path == asset.path) {
+            None => changes.push(format!("Added {} {}", kind, file_name(asset))),
+            Some(previous) if previous.enabled != asset.enabled => changes.push(format!(
+                "{} {} {}",
+                if asset.enabled { "Enabled" } else { "Disabled" },
+                kind,
+                file_name(asset)
+            )),
+            Some(_) => {}
+        }
+    }
+
+    let kept_order = |assets: &[Asset], others: &[Asset]| -> Vec<PathBuf> {
+        assets
+            .iter()
+            .filter(|asset| others.iter().any(|other| other.path == asset.path))
+            .map(|asset| asset.path.clone())
+            .collect()
+    };
+    if kept_order(old, new) != kept_order(new, old) {
+        changes.push(format!("Changed {} load order", kind));
+    }
+
+    changes
+}
+
+fn param_changes(old: &[OsString], new: &[OsString]) -> Vec<String> {
+    let removed = old
+        .iter()
+        .filter(|param| !new.contains(param))
+        .map(|param| format!("Removed parameter {}", param.to_string_lossy()));
+    let added = new
+        .iter()
+        .filter(|param| !old.contains(param))
+        .map(|param| format!("Added parameter {}", param.to_string_lossy()));
+    removed.chain(added).collect()
+}
+
+/// Human readable list of what changed between two versions of an
+/// instance's game data.
+pub fn describe_changes(old: &GameData, new: &GameData) -> Vec<String> {
+    let iwads = |gamedata: &GameData| -> Vec<Asset> {
+        gamedata.iwads.iter().map(|iwad| iwad.0.clone()).collect()
+    };
+    let mods = |gamedata: &GameData| -> Vec<Asset> {
+        gamedata.mods.iter().map(|mod_| mod_.0.clone()).collect()
+    };
+
+    let mut changes = asset_changes("IWAD", &iwads(old), &iwads(new));
+    changes.extend(asset_changes("mod", &mods(old), &mods(new)));
+
+    if old.savedir != new.savedir {
+        changes.push(format!(
+            "Changed save directory from '{}' to '{}'",
+            old.savedir.to_string_lossy(),
+            new.savedir.to_string_lossy()
+        ));
+    }
+
+    changes.extend(param_changes(
+        &old.additional_params,
+        &new.additional_params,
+    ));
+    changes
+}
+
+/// One line per asset, prefixed with `+`/`-` for enabled/disabled. Assets are
+/// shown by file name unless `full_paths` is set.
+pub fn asset_lines<'a>(
+    assets: impl IntoIterator<Item = &'a Asset>,
+    full_paths: bool,
+) -> Vec<String> {
+    assets
+        .into_iter()
+        .map(|asset| {
+            format!(
+                "{} {}",
+                if asset.enabled { "+" } else { "-" },
+                if full_paths {
+                    asset.path.to_string_lossy().to_string()
+                } else {
+                    file_name(asset)
+                }
+            )
+        })
+        .collect()
+}
+
+/// Parts of an instance's setup that can be compared and copied between instances.
+#[derive(Clone, Copy)]
+pub enum Field {
+    Iwads,
+    Mods,
+    Patches,
+    SaveDir,
+    AdditionalParams,
+    Engine,
+    Wrappers,
+    Environment,
+}
+
+impl Field {
+    const ALL: [Field; 8] = [
+        Field::Iwads,
+        Field::Mods,
+        Field::Patches,
+        Field::SaveDir,
+        Field::AdditionalParams,
+        Field::Engine,
+        Field::Wrappers,
+        Field::Environment,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Iwads => "IWADs",
+            Field::Mods => "Mods",
+            Field::Patches => "DeHackEd Patches",
+            Field::SaveDir => "Save Directory",
+            Field::AdditionalParams => "Additional Parameters",
+            Field::Engine => "Engine",
+            Field::Wrappers => "Wrappers",
+            Field::Environment => "Environment",
+        }
+    }
+
+    /// One line per entry, with assets as given by [`asset_lines`].
+    fn lines(&self, instance: &Instance, extras: &InstanceExtras, full_paths: bool) -> Vec<String> {
+        match self {
+            Field::Iwads => asset_lines(
+                instance.gamedata.iwads.iter().map(|iwad| &iwad.0),
+                full_paths,
+            ),
+            Field::Mods => asset_lines(
+                instance.gamedata.mods.iter().map(|mod_| &mod_.0),
+                full_paths,
+            ),
+            Field::Patches => asset_lines(extras.patches.iter().map(|patch| &patch.0), full_paths),
+            Field::SaveDir => vec![instance.gamedata.savedir.to_string_lossy().to_string()],
+            Field::AdditionalParams => instance
+                .gamedata
+                .additional_params
+                .iter()
+                .map(|param| param.to_string_lossy().to_string())
+                .collect(),
+            Field::Engine => vec![extras.engine.clone().unwrap_or("(global)".to_string())],
+            Field::Wrappers => extras.wrappers.clone(),
+            Field::Environment => extras
+                .env
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect(),
+        }
+    }
+
+    pub fn describe(&self, instance: &Instance, extras: &InstanceExtras) -> String {
+        self.lines(instance, extras, false).join("\n")
+    }
+
+    /// Copies this part of the setup from one instance to another.
+    pub fn copy(
+        &self,
+        (from, from_extras): (&Instance, &InstanceExtras),
+        (to, to_extras): (&mut Instance, &mut InstanceExtras),
+    ) {
+        match self {
+            Field::Iwads => to.gamedata.iwads = from.gamedata.iwads.clone(),
+            Field::Mods => to.gamedata.mods = from.gamedata.mods.clone(),
+            Field::Patches => to_extras.patches = from_extras.patches.clone(),
+            Field::SaveDir => to.gamedata.savedir = from.gamedata.savedir.clone(),
+            Field::AdditionalParams => {
+                to.gamedata.additional_params = from.gamedata.additional_params.clone()
+            }
+            Field::Engine => {
+                to_extras.engine = from_extras.engine.clone();
+                to_extras.engine_version = None;
+            }
+            Field::Wrappers => to_extras.wrappers = from_extras.wrappers.clone(),
+            Field::Environment => to_extras.env = from_extras.env.clone(),
+        }
+    }
+}
+
+/// Fields whose values differ between the two instances.
+pub fn differing_fields(
+    (a, a_extras): (&Instance, &InstanceExtras),
+    (b, b_extras): (&Instance, &InstanceExtras),
+) -> Vec<Field> {
+    Field::ALL
+        .into_iter()
+        .filter(|field| field.lines(a, a_extras, true) != field.lines(b, b_extras, true))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sulphur_core::{Iwad, Mod};
+
+    fn asset(path: &str, enabled: bool) -> Asset {
+        Asset {
+            path: PathBuf::from(path),
+            enabled,
+        }
+    }
+
+    fn gamedata(mods: &[(&str, bool)], params: &[&str]) -> GameData {
+        GameData {
+            iwads: vec![Iwad(asset("/wads/doom2.wad", true))],
+            mods: mods
+                .iter()
+                .map(|&(path, enabled)| Mod(asset(path, enabled)))
+                .collect(),
+            savedir: PathBuf::from("/saves"),
+            additional_params: params.iter().map(OsString::from).collect(),
+        }
+    }
+
+    #[test]
+    fn no_changes_for_equal_game_data() {
+        let data = gamedata(&[("/mods/a.pk3", true)], &["-fast"]);
+        assert!(describe_changes(&data, &data.clone()).is_empty());
+    }
+
+    #[test]
+    fn describes_added_removed_and_toggled_mods() {
+        let old = gamedata(&[("/mods/a.pk3", true), ("/mods/b.pk3", true)], &[]);
+        let new = gamedata(&[("/mods/b.pk3", false), ("/mods/c.wad", true)], &[]);
+        assert_eq!(
+            describe_changes(&old, &new),
+            ["Removed mod a.pk3", "Disabled mod b.pk3", "Added mod c.wad"]
+        );
+    }
+
+    #[test]
+    fn describes_load_order_savedir_and_parameters() {
+        let old = gamedata(&[("/mods/a.pk3", true), ("/mods/b.pk3", true)], &["-fast"]);
+        let mut new = gamedata(
+            &[("/mods/b.pk3", true), ("/mods/a.pk3", true)],
+            &["-nomonsters"],
+        );
+        new.savedir = PathBuf::from("/other");
+        assert_eq!(
+            describe_changes(&old, &new),
+            [
+                "Changed mod load order",
+                "Changed save directory from '/saves' to '/other'",
+                "Removed parameter -fast",
+                "Added parameter -nomonsters",
+            ]
+        );
+    }
+}
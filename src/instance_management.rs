@@ -1,102 +1,605 @@
 use anyhow::Result;
-use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use sulphur_core::SulphurConfig;
 use sulphur_core::{GameData, Instance, Metadata};
+use tabled::{builder::Builder, settings::Style as TabledStyle};
 
-use crate::asset_management::{additional_params_management, iwad_management, mod_management};
-use crate::file_utils::select_file;
+use crate::asset_management::{
+    additional_params_management, enabled_wads, hooks_management, iwad_management,
+    launch_environment_management, mod_management, patch_management,
+};
+use crate::cli_config::{data_dir, storage_dir, CliConfig, CliSettings, InstanceExtras};
+use crate::command::{
+    get_full_command, is_lossy, print_dehacked_warnings, to_json_array, to_shell_line,
+    to_shell_script,
+};
+use crate::duration_utils::parse_duration;
+use crate::file_utils::{
+    add_text_to_zip, copy_dir_all, copy_to_clipboard, move_to_trash, read_text_from_zip,
+    safe_file_name, select_file,
+};
+use crate::image_utils::{encode_png, extract_titlepic};
+use crate::input::{Confirm, Editor, Input, Select};
+use crate::instance_diff::{differing_fields, Field};
 use crate::menu::{Menu, BACK_BUTTON};
+use crate::profile::{other_configs, ConfigLocation};
+use crate::ui::show_instance_details;
 
+/// Entry added to exported `.brimpkg` files holding the instance notes.
+const BRIMPKG_NOTES_FILE: &str = "notes.txt";
+
+// Only ever returned from the edit menu, never stored.
+#[allow(clippy::large_enum_variant)]
 pub enum InstanceManagementExitState {
-    Some(Instance),
+    Some(Instance, InstanceExtras),
     None,
     Delete,
+    Archive,
 }
 
-pub fn create_new_instance() -> Result<InstanceManagementExitState> {
-    edit_single_instance(Instance {
-        metadata: Metadata {
-            name: "".to_string(),
-            image: None,
-            playtime: Default::default(),
-            last_played: None,
-            last_session_duration: None,
-        },
-        gamedata: GameData {
-            iwads: vec![],
-            mods: vec![],
-            savedir: Default::default(),
-            additional_params: vec![],
+/// Names are how instances and their CLI data are told apart, so they must be
+/// unique. `taken` holds the names of every other instance.
+pub fn validate_instance_name(name: &str, taken: &[String]) -> Result<(), String> {
+    if name.trim().is_empty() {
+        Err("The name can't be empty".to_string())
+    } else if taken.iter().any(|other| other == name) {
+        Err(format!("An instance named '{}' already exists", name))
+    } else {
+        Ok(())
+    }
+}
+
+/// Names of all instances except the one at `except`, archived ones included.
+pub fn instance_names(config: &SulphurConfig, except: Option<usize>) -> Vec<String> {
+    config
+        .instances
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| Some(index) != except)
+        .map(|(_, instance)| instance.metadata.name.clone())
+        .collect()
+}
+
+pub fn create_new_instance(
+    settings: &CliSettings,
+    global_command: &OsStr,
+    taken_names: &[String],
+) -> Result<InstanceManagementExitState> {
+    edit_single_instance(
+        Instance {
+            metadata: Metadata {
+                name: "".to_string(),
+                image: None,
+                playtime: Default::default(),
+                last_played: None,
+                last_session_duration: None,
+            },
+            gamedata: GameData {
+                iwads: vec![],
+                mods: vec![],
+                savedir: Default::default(),
+                additional_params: vec![],
+            },
         },
-    })
+        InstanceExtras::default(),
+        settings,
+        global_command,
+        taken_names,
+    )
 }
 
-pub fn manage_instances(config: &mut SulphurConfig, indexes: &[usize]) -> Result<()> {
-    let selection = instance_selection(config, indexes)?;
+pub fn manage_instances(
+    location: &ConfigLocation,
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    indexes: &[usize],
+) -> Result<()> {
+    let selection = instance_selection(config, cli_config, indexes)?;
 
     if let Some(instance_index) = selection {
+        let global_command = config.get_command();
         let instance = &config.instances[instance_index];
+        let extras = cli_config.extras(&instance.metadata.name);
 
-        match edit_single_instance(instance.clone())? {
-            InstanceManagementExitState::Some(edited_instance) => {
+        match edit_single_instance(
+            instance.clone(),
+            extras,
+            &cli_config.settings,
+            global_command.as_os_str(),
+            &instance_names(config, Some(instance_index)),
+        )? {
+            InstanceManagementExitState::Some(edited_instance, mut edited_extras) => {
+                edited_extras.record_revision(&instance.gamedata, &edited_instance.gamedata);
+                cli_config.set_extras(
+                    &instance.metadata.name,
+                    &edited_instance.metadata.name,
+                    edited_extras,
+                )?;
                 config.instances[instance_index] = edited_instance;
             }
             InstanceManagementExitState::None => {
                 println!("Cancelled!");
             }
             InstanceManagementExitState::Delete => {
-                if Confirm::with_theme(&ColorfulTheme::default())
-                    .with_prompt(&format!(
-                        "Are you sure you want to delete '{}'?",
-                        instance.metadata.name
-                    ))
-                    .default(false)
-                    .interact()?
-                {
-                    config.instances.remove(instance_index);
+                if delete_instance(location, config, cli_config, instance_index)? {
                     return Ok(());
                 }
             }
+            InstanceManagementExitState::Archive => {
+                let name = instance.metadata.name.clone();
+                let mut extras = cli_config.extras(&name);
+                extras.archived = true;
+                cli_config.set_extras(&name, &name, extras)?;
+                println!("Archived '{}'.", name);
+                return Ok(());
+            }
         }
     } else {
         return Ok(());
     }
-    manage_instances(config, indexes)
+    manage_instances(location, config, cli_config, indexes)
 }
 
-pub fn instance_selection(config: &SulphurConfig, indexes: &[usize]) -> Result<Option<usize>> {
-    let instances = config.get_instances();
-    let instance_names: Vec<&str> = indexes
+/// Save directories, assets and images used by the instances and templates of
+/// a config, leaving out the instance at `except`.
+fn referenced_paths(
+    config: &SulphurConfig,
+    cli_config: &CliConfig,
+    except: Option<usize>,
+) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = vec![];
+    let instances = config
+        .instances
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| Some(index) != except)
+        .map(|(_, instance)| instance);
+    for instance in instances.clone() {
+        paths.extend(instance.metadata.image.clone());
+    }
+    let instances = instances.map(|instance| {
+        (
+            &instance.gamedata,
+            cli_config.extras(&instance.metadata.name),
+        )
+    });
+    let templates = cli_config
+        .templates
+        .values()
+        .map(|template| (&template.gamedata, template.extras.clone()));
+    for (gamedata, extras) in instances.chain(templates) {
+        paths.push(gamedata.savedir.clone());
+        paths.extend(gamedata.iwads.iter().map(|iwad| iwad.0.path.clone()));
+        paths.extend(gamedata.mods.iter().map(|mod_| mod_.0.path.clone()));
+        paths.extend(extras.patches.iter().map(|patch| patch.0.path.clone()));
+    }
+    paths
+}
+
+/// Files only the instance at `index` uses: its save directory and the assets
+/// and image that were moved into Sulphur's data directory. The data
+/// directory is shared by every profile, so their configs are checked too.
+fn leftover_files(
+    location: &ConfigLocation,
+    config: &SulphurConfig,
+    cli_config: &CliConfig,
+    index: usize,
+) -> Result<Vec<PathBuf>> {
+    let instance = &config.instances[index];
+    let extras = cli_config.extras(&instance.metadata.name);
+
+    let mut referenced_elsewhere = referenced_paths(config, cli_config, Some(index));
+    for (other_config, other_cli_config) in other_configs(location)? {
+        referenced_elsewhere.extend(referenced_paths(&other_config, &other_cli_config, None));
+    }
+
+    let data_dir = data_dir()?;
+    let moved_files = instance
+        .gamedata
+        .iwads
         .iter()
-        .map(|&index| instances[index].metadata.name.as_str())
+        .map(|iwad| iwad.0.path.clone())
+        .chain(
+            instance
+                .gamedata
+                .mods
+                .iter()
+                .map(|mod_| mod_.0.path.clone()),
+        )
+        .chain(extras.patches.iter().map(|patch| patch.0.path.clone()))
+        .chain(instance.metadata.image.clone())
+        .filter(|path| path.starts_with(&data_dir));
+
+    // Save directories may be nested in each other, so anything containing or
+    // inside a path still in use has to stay too.
+    referenced_elsewhere.retain(|path| !path.as_os_str().is_empty());
+    let in_use = |path: &Path| {
+        referenced_elsewhere
+            .iter()
+            .any(|other| path.starts_with(other) || other.starts_with(path))
+    };
+
+    let mut leftovers: Vec<PathBuf> = vec![];
+    for path in std::iter::once(instance.gamedata.savedir.clone()).chain(moved_files) {
+        if !path.as_os_str().is_empty()
+            && path.exists()
+            && !in_use(&path)
+            && !leftovers.contains(&path)
+        {
+            leftovers.push(path);
+        }
+    }
+    Ok(leftovers)
+}
+
+/// Removes an instance after asking for confirmation, offering to trash the
+/// files nothing else uses. Returns whether it was deleted.
+fn delete_instance(
+    location: &ConfigLocation,
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    index: usize,
+) -> Result<bool> {
+    let name = config.instances[index].metadata.name.clone();
+    let leftovers = leftover_files(location, config, cli_config, index)?;
+    if !leftovers.is_empty() {
+        println!("Deleting '{}' leaves these behind:", name);
+        for path in &leftovers {
+            println!("  {}", path.to_string_lossy());
+        }
+    }
+
+    if !Confirm::new()
+        .with_prompt(format!("Are you sure you want to delete '{}'?", name))
+        .default(false)
+        .interact()?
+    {
+        return Ok(false);
+    }
+
+    if !leftovers.is_empty()
+        && Confirm::new()
+            .with_prompt("Move them to the trash?")
+            .default(false)
+            .interact()?
+    {
+        for path in &leftovers {
+            if let Err(error) = move_to_trash(path) {
+                println!(
+                    "Warning: could not trash '{}': {}",
+                    path.to_string_lossy(),
+                    error
+                );
+            }
+        }
+    }
+
+    cli_config.instances.remove(&name);
+    config.instances.remove(index);
+    Ok(true)
+}
+
+/// Lists archived instances so they can be restored or deleted for good.
+pub fn archived_instances(
+    location: &ConfigLocation,
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+) -> Result<()> {
+    loop {
+        let archived: Vec<usize> = (0..config.instances.len())
+            .filter(|&index| {
+                cli_config
+                    .instances
+                    .get(&config.instances[index].metadata.name)
+                    .is_some_and(|extras| extras.archived)
+            })
+            .collect();
+        if archived.is_empty() {
+            println!("No archived instances!");
+            return Ok(());
+        }
+
+        let Some(index) = instance_selection(config, cli_config, &archived)? else {
+            return Ok(());
+        };
+        let name = config.instances[index].metadata.name.clone();
+
+        match Select::new()
+            .with_prompt(format!("'{}'", name))
+            .items(Menu::ArchivedInstanceMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => {
+                let mut extras = cli_config.extras(&name);
+                extras.archived = false;
+                cli_config.set_extras(&name, &name, extras)?;
+                println!("Restored '{}'.", name);
+            }
+            1 => {
+                delete_instance(location, config, cli_config, index)?;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Copies an instance's setup under a new name, with fresh playtime and its
+/// own save directory.
+pub fn duplicate_instance(
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    indexes: &[usize],
+) -> Result<()> {
+    let Some(source_index) = instance_selection(config, cli_config, indexes)? else {
+        return Ok(());
+    };
+    let source = &config.instances[source_index];
+
+    let mut duplicate = source.clone();
+    duplicate.metadata.playtime = Default::default();
+    duplicate.metadata.last_played = None;
+    duplicate.metadata.last_session_duration = None;
+
+    let taken_names = instance_names(config, None);
+    duplicate.metadata.name = Input::new()
+        .with_prompt("Enter name for the copy")
+        .default(format!("{} (copy)", source.metadata.name))
+        .validate_with(|input: &String| validate_instance_name(input, &taken_names))
+        .interact_text()?;
+
+    duplicate.initialize_absolute_savedir()?;
+    if duplicate.gamedata.savedir == source.gamedata.savedir {
+        println!("The copy would share its save directory with the original, cancelling.");
+        return Ok(());
+    }
+
+    if source.gamedata.savedir.exists()
+        && Confirm::new()
+            .with_prompt("Do you want to copy the existing saves?")
+            .default(false)
+            .interact()?
+    {
+        copy_dir_all(&source.gamedata.savedir, &duplicate.gamedata.savedir)?;
+    } else {
+        duplicate.create_savedir()?;
+    }
+
+    let mut extras = cli_config.extras(&source.metadata.name).setup();
+    extras.created = Some(SystemTime::now());
+    cli_config.add_extras(&duplicate.metadata.name, extras)?;
+    println!(
+        "Created '{}' from '{}'.",
+        duplicate.metadata.name, source.metadata.name
+    );
+    config.instances.push(duplicate);
+    Ok(())
+}
+
+/// Shows where two instances differ side by side and lets individual
+/// differences be copied from one to the other.
+pub fn compare_instances(
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    indexes: &[usize],
+) -> Result<()> {
+    println!("Choose the first instance.");
+    let Some(left_index) = instance_selection(config, cli_config, indexes)? else {
+        return Ok(());
+    };
+    let others: Vec<usize> = indexes
+        .iter()
+        .copied()
+        .filter(|&i| i != left_index)
         .collect();
+    println!("Choose the instance to compare it with.");
+    let Some(right_index) = instance_selection(config, cli_config, &others)? else {
+        return Ok(());
+    };
+
+    loop {
+        let left = &config.instances[left_index];
+        let right = &config.instances[right_index];
+        let left_extras = cli_config.extras(&left.metadata.name);
+        let right_extras = cli_config.extras(&right.metadata.name);
+
+        let fields = differing_fields((left, &left_extras), (right, &right_extras));
+        if fields.is_empty() {
+            println!(
+                "'{}' and '{}' have the same setup.",
+                left.metadata.name, right.metadata.name
+            );
+            return Ok(());
+        }
+
+        let mut table = Builder::new();
+        table.push_record([
+            "",
+            left.metadata.name.as_str(),
+            right.metadata.name.as_str(),
+        ]);
+        for field in &fields {
+            table.push_record([
+                field.name().to_string(),
+                field.describe(left, &left_extras),
+                field.describe(right, &right_extras),
+            ]);
+        }
+        let mut table = table.build();
+        table.with(TabledStyle::extended());
+        println!("{}", table);
+
+        let mut actions: Vec<(Field, bool)> = vec![];
+        let mut menu_items: Vec<String> = vec![];
+        for &field in &fields {
+            for left_to_right in [true, false] {
+                let (from, to) = if left_to_right {
+                    (&left.metadata.name, &right.metadata.name)
+                } else {
+                    (&right.metadata.name, &left.metadata.name)
+                };
+                actions.push((field, left_to_right));
+                menu_items.push(format!("Copy {} from '{}' to '{}'", field.name(), from, to));
+            }
+        }
+        menu_items.push(BACK_BUTTON.to_string());
+
+        let selection = Select::new()
+            .with_prompt("Choose Action")
+            .default(0)
+            .items(&menu_items)
+            .interact()?;
+        let Some(&(field, left_to_right)) = actions.get(selection) else {
+            return Ok(());
+        };
+
+        let (from_index, to_index) = if left_to_right {
+            (left_index, right_index)
+        } else {
+            (right_index, left_index)
+        };
+        let from = config.instances[from_index].clone();
+        let mut to = config.instances[to_index].clone();
+        let from_extras = cli_config.extras(&from.metadata.name);
+        let mut to_extras = cli_config.extras(&to.metadata.name);
+
+        field.copy((&from, &from_extras), (&mut to, &mut to_extras));
+        if let Field::SaveDir = field {
+            println!(
+                "Warning: '{}' and '{}' now share a save directory.",
+                from.metadata.name, to.metadata.name
+            );
+        }
+
+        to_extras.record_revision(&config.instances[to_index].gamedata, &to.gamedata);
+        cli_config.set_extras(&to.metadata.name, &to.metadata.name, to_extras)?;
+        config.instances[to_index] = to;
+    }
+}
+
+/// Entry of the instance list that searches the instances' files instead.
+const SEARCH_BY_FILE: &str = "Search by File Name";
+
+/// Searchable line for an instance: its name and tags, so that typing either
+/// finds it.
+fn instance_search_label(instance: &Instance, extras: Option<&InstanceExtras>) -> String {
+    let mut label = instance.metadata.name.clone();
+    if let Some(extras) = extras.filter(|extras| !extras.tags.is_empty()) {
+        label.push_str(&format!("  [{}]", extras.tags.join(", ")));
+    }
+    label
+}
+
+/// File names of all of an instance's IWADs, mods and patches, disabled ones
+/// included.
+fn asset_file_names(instance: &Instance, extras: Option<&InstanceExtras>) -> Vec<String> {
+    let iwads = instance.gamedata.iwads.iter().map(|iwad| &iwad.0);
+    let mods = instance.gamedata.mods.iter().map(|mod_| &mod_.0);
+    let patches = extras
+        .into_iter()
+        .flat_map(|extras| extras.patches.iter().map(|patch| &patch.0));
+    iwads
+        .chain(mods)
+        .chain(patches)
+        .filter_map(|asset| asset.path.file_name())
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .collect()
+}
 
-    let mut menu_items = instance_names;
-    menu_items.push(BACK_BUTTON);
+/// Lists the instances using a file whose name contains the text asked for,
+/// each with the file that matched.
+fn file_search_selection(
+    config: &SulphurConfig,
+    cli_config: &CliConfig,
+    indexes: &[usize],
+) -> Result<Option<usize>> {
+    let query: String = Input::new()
+        .with_prompt("File name contains")
+        .interact_text()?;
+    let query = query.trim().to_lowercase();
 
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Choose Instance")
+    let mut matches: Vec<usize> = vec![];
+    let mut menu_items: Vec<String> = vec![];
+    for &index in indexes {
+        let instance = &config.instances[index];
+        let extras = cli_config.instances.get(&instance.metadata.name);
+        let Some(file) = asset_file_names(instance, extras)
+            .into_iter()
+            .find(|file| file.to_lowercase().contains(&query))
+        else {
+            continue;
+        };
+        matches.push(index);
+        menu_items.push(format!(
+            "{}  ({})",
+            instance_search_label(instance, extras),
+            file
+        ));
+    }
+    if matches.is_empty() {
+        println!("No instance uses a file matching '{}'.", query);
+        return instance_selection(config, cli_config, indexes);
+    }
+    menu_items.push(BACK_BUTTON.to_string());
+
+    let selection = Select::fuzzy()
+        .with_prompt("Choose Instance (type to search)")
         .default(0)
         .items(&menu_items)
         .interact()?;
+    match matches.get(selection) {
+        Some(&index) => Ok(Some(index)),
+        None => instance_selection(config, cli_config, indexes),
+    }
+}
 
-    let back_button_index = indexes.len();
-    if selection == back_button_index {
-        Ok(None)
-    } else {
-        Ok(Some(indexes[selection]))
+pub fn instance_selection(
+    config: &SulphurConfig,
+    cli_config: &CliConfig,
+    indexes: &[usize],
+) -> Result<Option<usize>> {
+    let instances = config.get_instances();
+    let mut menu_items: Vec<String> = indexes
+        .iter()
+        .map(|&index| {
+            let instance = &instances[index];
+            instance_search_label(instance, cli_config.instances.get(&instance.metadata.name))
+        })
+        .collect();
+    menu_items.push(SEARCH_BY_FILE.to_string());
+    menu_items.push(BACK_BUTTON.to_string());
+
+    let selection = Select::fuzzy()
+        .with_prompt("Choose Instance (type to search)")
+        .default(0)
+        .items(&menu_items)
+        .interact()?;
+
+    match selection {
+        index if index < indexes.len() => Ok(Some(indexes[index])),
+        index if index == indexes.len() => file_search_selection(config, cli_config, indexes),
+        _ => Ok(None),
     }
 }
 
-pub fn edit_single_instance(instance: Instance) -> Result<InstanceManagementExitState> {
+pub fn edit_single_instance(
+    instance: Instance,
+    extras: InstanceExtras,
+    settings: &CliSettings,
+    global_command: &OsStr,
+    taken_names: &[String],
+) -> Result<InstanceManagementExitState> {
     let mut result = instance.clone();
+    let mut result_extras = extras;
     let initial_savedir = result.gamedata.savedir.clone();
     loop {
         let selection = if !result.metadata.name.is_empty() {
-            Select::with_theme(&ColorfulTheme::default())
+            Select::new()
                 .with_prompt("Choose Action")
-                .items(&Menu::EditInstanceMenu.options())
+                .items(Menu::EditInstanceMenu.options())
                 .default(0)
                 .interact()?
         } else {
@@ -105,12 +608,13 @@ pub fn edit_single_instance(instance: Instance) -> Result<InstanceManagementExit
 
         match selection {
             0 => {
-                result.metadata.name = Input::with_theme(&ColorfulTheme::default())
+                result.metadata.name = Input::new()
                     .with_prompt("Enter instance name")
                     .default(result.metadata.name.clone())
+                    .validate_with(|input: &String| validate_instance_name(input, taken_names))
                     .interact_text()?;
-                if Confirm::with_theme(&ColorfulTheme::default())
-                    .with_prompt(&format!(
+                if Confirm::new()
+                    .with_prompt(format!(
                         "Want to update the saves directory of '{}'?",
                         result.metadata.name
                     ))
@@ -122,37 +626,68 @@ pub fn edit_single_instance(instance: Instance) -> Result<InstanceManagementExit
             }
             1 => iwad_management(&mut result.gamedata)?,
             2 => mod_management(&mut result.gamedata)?,
-            3 => {
+            3 => patch_management(&mut result_extras)?,
+            4 => {
                 println!(
                     "Current save directory: '{}'",
                     result.gamedata.savedir.to_string_lossy()
                 );
-                if Confirm::with_theme(&ColorfulTheme::default())
+                if Confirm::new()
                     .with_prompt("Do you want to change it?")
                     .default(false)
                     .interact()?
-                {
-                    if let Some(new_path) = select_file(
+                    && let Some(new_path) = select_file(
                         "Choose new save folder",
                         true,
                         None,
                         Some(result.gamedata.savedir.clone()),
-                    )? {
-                        result.gamedata.savedir = new_path;
-                    }
+                    )?
+                {
+                    result.gamedata.savedir = new_path;
+                }
+            }
+            5 => additional_params_management(&mut result.gamedata)?,
+            6 => {
+                let input: String = Input::new()
+                    .with_prompt("Enter engine command (leave empty to use the global one)")
+                    .default(result_extras.engine.clone().unwrap_or_default())
+                    .allow_empty(true)
+                    .interact_text()?;
+                let engine = Some(input.trim().to_string()).filter(|engine| !engine.is_empty());
+                if engine != result_extras.engine {
+                    result_extras.engine = engine;
+                    result_extras.engine_version = None;
                 }
             }
-            4 => additional_params_management(&mut result.gamedata)?,
-            5 => println!(
-                "Full command: {}",
-                result
-                    .get_full_command(OsString::from("gzdoom").as_os_str())
-                    .to_string_lossy()
-            ),
-            6 => return Ok(InstanceManagementExitState::Delete),
             7 => {
+                launch_environment_management(&mut result_extras.wrappers, &mut result_extras.env)?
+            }
+            8 => hooks_management(&mut result_extras.hooks)?,
+            9 => organise_instance(&mut result_extras)?,
+            10 => {
+                if let Some(notes) = Editor::new().edit(&result_extras.notes)? {
+                    result_extras.notes = notes.trim_end().to_string();
+                }
+            }
+            11 => set_instance_image(&mut result)?,
+            12 => show_instance_details(&result, &result_extras, settings),
+            13 => {
+                if let Some(previous) = revision_selection(&result_extras, settings)? {
+                    result.gamedata = previous;
+                    println!("Reverted, save the changes to keep it.");
+                }
+            }
+            14 => playtime_management(&mut result, &mut result_extras, settings)?,
+            15 => {
+                print_dehacked_warnings(&result, &result_extras);
+                let argv = get_full_command(&result, &result_extras, settings, global_command);
+                full_command_preview(&result.metadata.name, &argv)?;
+            }
+            16 => return Ok(InstanceManagementExitState::Archive),
+            17 => return Ok(InstanceManagementExitState::Delete),
+            18 => {
                 if initial_savedir.exists() {
-                    if &initial_savedir != &result.gamedata.savedir {
+                    if initial_savedir != result.gamedata.savedir {
                         println!(
                             "The old save directory '{}' has been moved to {}.",
                             initial_savedir.to_string_lossy(),
@@ -163,29 +698,353 @@ pub fn edit_single_instance(instance: Instance) -> Result<InstanceManagementExit
                 } else {
                     result.create_savedir()?;
                 }
-                return Ok(InstanceManagementExitState::Some(result));
+                return Ok(InstanceManagementExitState::Some(result, result_extras));
             }
-            8 => {
-                if Confirm::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Are you sure you want to exit? Unsaved changes will be lost.")
-                    .default(false)
-                    .interact()?
+            19 if Confirm::new()
+                .with_prompt("Are you sure you want to exit? Unsaved changes will be lost.")
+                .default(false)
+                .interact()? =>
+            {
+                return Ok(InstanceManagementExitState::None);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Corrects the recorded playtime by hand and edits the session log.
+/// Deleting or trimming a session also takes the time off the total.
+fn playtime_management(
+    instance: &mut Instance,
+    extras: &mut InstanceExtras,
+    settings: &CliSettings,
+) -> Result<()> {
+    let style = settings.duration_style;
+    loop {
+        println!(
+            "Total playtime: {}",
+            style.format(instance.metadata.playtime)
+        );
+        match Select::new()
+            .with_prompt("Choose Action")
+            .items(Menu::PlaytimeMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => {
+                let amount = duration_input("Add how much playtime? (e.g. 2h 30m)")?;
+                instance.metadata.playtime += amount;
+            }
+            1 => {
+                let amount = duration_input("Subtract how much playtime? (e.g. 2h 30m)")?;
+                instance.metadata.playtime = instance.metadata.playtime.saturating_sub(amount);
+            }
+            2 => {
+                if let Some(index) = session_selection(extras, settings)? {
+                    let session = extras.sessions.remove(index);
+                    instance.metadata.playtime =
+                        instance.metadata.playtime.saturating_sub(session.duration);
+                    sync_last_session(instance, extras, session.started_at);
+                }
+            }
+            3 => {
+                if let Some(index) = session_selection(extras, settings)? {
+                    let session = &mut extras.sessions[index];
+                    let new_duration = duration_input(&format!(
+                        "New length of the session (currently {})",
+                        style.format(session.duration)
+                    ))?;
+                    if new_duration < session.duration {
+                        instance.metadata.playtime = instance
+                            .metadata
+                            .playtime
+                            .saturating_sub(session.duration - new_duration);
+                        session.duration = new_duration;
+                        let started_at = session.started_at;
+                        sync_last_session(instance, extras, started_at);
+                    } else {
+                        println!("Sessions can only be shortened.");
+                    }
+                }
+            }
+            4 => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+/// Updates the last played time and last session length after the session
+/// started at `changed` was deleted or shortened. They are only taken from the
+/// newest session left when they came from the changed one or the log has a
+/// newer one, as they may predate the log; an emptied log leaves them alone.
+fn sync_last_session(instance: &mut Instance, extras: &InstanceExtras, changed: SystemTime) {
+    let Some(newest) = extras
+        .sessions
+        .iter()
+        .max_by_key(|session| session.started_at)
+    else {
+        return;
+    };
+    let last_played = instance.metadata.last_played;
+    if last_played == Some(changed) || last_played.is_none_or(|last| newest.started_at > last) {
+        instance.metadata.last_played = Some(newest.started_at);
+        instance.metadata.last_session_duration = Some(newest.duration);
+    }
+}
+
+fn duration_input(prompt: &str) -> Result<std::time::Duration> {
+    let input: String = Input::new()
+        .with_prompt(prompt)
+        .validate_with(|input: &String| -> Result<(), &str> {
+            match parse_duration(input) {
+                Some(_) => Ok(()),
+                None => Err("Enter a duration like 1h 20m 5s"),
+            }
+        })
+        .interact_text()?;
+    Ok(parse_duration(&input).unwrap_or_default())
+}
+
+/// Lets the user pick a recorded session, newest first.
+fn session_selection(extras: &InstanceExtras, settings: &CliSettings) -> Result<Option<usize>> {
+    let style = settings.duration_style;
+    if extras.sessions.is_empty() {
+        println!("No sessions recorded yet!");
+        return Ok(None);
+    }
+
+    let mut menu_items: Vec<String> = extras
+        .sessions
+        .iter()
+        .rev()
+        .map(|session| {
+            let mut item = format!(
+                "{}  {}",
+                settings.format_time(session.started_at),
+                style.format(session.duration)
+            );
+            if let Some(raw) = session.raw_duration.filter(|&raw| raw != session.duration) {
+                item.push_str(&format!(" (ran {})", style.format(raw)));
+            }
+            if let Some(map) = &session.map {
+                item.push_str(&format!("  {}", map));
+            }
+            item
+        })
+        .collect();
+    menu_items.push(BACK_BUTTON.to_string());
+
+    let selection = Select::new()
+        .with_prompt("Choose Session")
+        .items(&menu_items)
+        .default(0)
+        .interact()?;
+    if selection == menu_items.len() - 1 {
+        Ok(None)
+    } else {
+        Ok(Some(extras.sessions.len() - 1 - selection))
+    }
+}
+
+/// Lets the user pick a saved revision, returning the game data from before it.
+fn revision_selection(extras: &InstanceExtras, settings: &CliSettings) -> Result<Option<GameData>> {
+    if extras.history.is_empty() {
+        println!("No saved changes yet!");
+        return Ok(None);
+    }
+
+    let mut menu_items: Vec<String> = extras
+        .history
+        .iter()
+        .rev()
+        .map(|revision| {
+            format!(
+                "{}  {}",
+                settings.format_time(revision.saved_at),
+                revision.changes.join("; ")
+            )
+        })
+        .collect();
+    menu_items.push(BACK_BUTTON.to_string());
+
+    let selection = Select::new()
+        .with_prompt("Choose a change to undo (newest first)")
+        .items(&menu_items)
+        .default(0)
+        .interact()?;
+    if selection == menu_items.len() - 1 {
+        return Ok(None);
+    }
+
+    let revision = &extras.history[extras.history.len() - 1 - selection];
+    for change in &revision.changes {
+        println!("  {}", change);
+    }
+    if Confirm::new()
+        .with_prompt("Revert to the state before this change?")
+        .default(false)
+        .interact()?
+    {
+        Ok(Some(revision.previous.clone()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn set_instance_image(instance: &mut Instance) -> Result<()> {
+    match Select::new()
+        .with_prompt("Choose Action")
+        .items(Menu::ImageMenu.options())
+        .default(0)
+        .interact()?
+    {
+        0 => {
+            if let Some(path) = select_file("Select image", false, Some(&["png"]), None)? {
+                instance.metadata.image = Some(path);
+            }
+        }
+        1 => {
+            let wads: Vec<PathBuf> = enabled_wads(instance)
+                .map(|asset| asset.path.clone())
+                .collect();
+
+            match extract_titlepic(&wads) {
+                Ok(Some(picture)) => {
+                    let file_name = format!("{}.png", safe_file_name(&instance.metadata.name));
+                    let save = || -> Result<PathBuf> {
+                        let image_path = storage_dir("images")?.join(file_name);
+                        std::fs::write(&image_path, encode_png(&picture)?)?;
+                        Ok(image_path)
+                    };
+                    match save() {
+                        Ok(image_path) => {
+                            println!("TITLEPIC saved to {}", image_path.to_string_lossy());
+                            instance.metadata.image = Some(image_path);
+                        }
+                        Err(error) => println!("Could not save the TITLEPIC: {}", error),
+                    }
+                }
+                Ok(None) => println!("None of the enabled WADs contain a TITLEPIC."),
+                Err(error) => println!("Could not read the TITLEPIC: {}", error),
+            }
+        }
+        2 => instance.metadata.image = None,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn organise_instance(extras: &mut InstanceExtras) -> Result<()> {
+    loop {
+        println!(
+            "Favourite: {}, Tags: [{}], Group: {}",
+            if extras.favourite { "yes" } else { "no" },
+            extras.tags.join(", "),
+            extras.group.as_deref().unwrap_or("None")
+        );
+
+        match Select::new()
+            .with_prompt("Choose Action")
+            .items(Menu::OrganiseMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => extras.favourite = !extras.favourite,
+            1 => {
+                let tag: String = Input::new().with_prompt("Enter tag").interact_text()?;
+                let tag = tag.trim().to_string();
+                if !extras.tags.contains(&tag) {
+                    extras.tags.push(tag);
+                }
+            }
+            2 => {
+                if extras.tags.is_empty() {
+                    println!("No tags to remove!");
+                    continue;
+                }
+
+                let mut tag_list = extras.tags.clone();
+                tag_list.push(BACK_BUTTON.to_string());
+
+                let selection = Select::new()
+                    .with_prompt("Select tag to remove")
+                    .items(&tag_list)
+                    .default(0)
+                    .interact()?;
+
+                if selection < extras.tags.len() {
+                    extras.tags.remove(selection);
+                }
+            }
+            3 => {
+                let group: String = Input::new()
+                    .with_prompt("Enter group (leave empty for none)")
+                    .default(extras.group.clone().unwrap_or_default())
+                    .allow_empty(true)
+                    .interact_text()?;
+                extras.group = Some(group.trim().to_string()).filter(|group| !group.is_empty());
+            }
+            4 => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+/// Shows the exact launch command and offers ways to reuse it outside of Sulphur.
+fn full_command_preview(name: &str, argv: &[OsString]) -> Result<()> {
+    let shell_line = to_shell_line(argv);
+    println!("Full command: {}", shell_line);
+    println!("As JSON: {}", to_json_array(argv));
+    if is_lossy(argv) {
+        println!("Warning: some arguments aren't valid UTF-8 and were altered in the JSON.");
+    }
+
+    loop {
+        match Select::new()
+            .with_prompt("Choose Action")
+            .items(Menu::FullCommandMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => match copy_to_clipboard(&shell_line) {
+                Ok(()) => println!("Command copied to clipboard!"),
+                Err(error) => println!("Could not copy to clipboard: {}", error),
+            },
+            1 => {
+                if let Some(folder) =
+                    select_file("Choose folder to write the script in", true, None, None)?
                 {
-                    return Ok(InstanceManagementExitState::None);
+                    let script_path = folder.join(format!("{}.sh", safe_file_name(name)));
+                    let write = || -> Result<()> {
+                        std::fs::write(&script_path, to_shell_script(argv))?;
+                        let mut permissions = std::fs::metadata(&script_path)?.permissions();
+                        permissions.set_mode(0o755);
+                        std::fs::set_permissions(&script_path, permissions)?;
+                        Ok(())
+                    };
+                    match write() {
+                        Ok(()) => println!("Script written to {}", script_path.to_string_lossy()),
+                        Err(error) => println!("Could not write the script: {}", error),
+                    }
                 }
             }
+            2 => return Ok(()),
             _ => {}
         }
     }
 }
 
-pub fn import_export(config: &mut SulphurConfig, indexes: &[usize]) -> Result<()> {
+pub fn import_export(
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    indexes: &[usize],
+) -> Result<()> {
     let mut selection = 0;
     loop {
         let menu_items = Menu::ImportExportMenu.options();
-        selection = Select::with_theme(&ColorfulTheme::default())
+        selection = Select::new()
             .with_prompt("Choose Action")
-            .items(&menu_items)
+            .items(menu_items)
             .default(selection)
             .interact()?;
         match selection {
@@ -196,40 +1055,74 @@ pub fn import_export(config: &mut SulphurConfig, indexes: &[usize]) -> Result<()
                     Some(&["zip", "brimpkg"]),
                     None,
                 )? {
-                    let new_instance_index =
-                        config.add_instance(Instance::load_brimpkg(file_path.as_path())?);
-                    println!(
-                        "Instance {} added!",
-                        config.instances[new_instance_index].metadata.name
-                    );
+                    let mut instance = Instance::load_brimpkg(file_path.as_path())?;
+                    let taken_names = instance_names(config, None);
+                    if taken_names.contains(&instance.metadata.name) {
+                        println!(
+                            "An instance named '{}' already exists.",
+                            instance.metadata.name
+                        );
+                        instance.metadata.name = Input::new()
+                            .with_prompt("Enter a name for the imported instance")
+                            .default(format!("{} (imported)", instance.metadata.name))
+                            .validate_with(|input: &String| {
+                                validate_instance_name(input, &taken_names)
+                            })
+                            .interact_text()?;
+                    }
+                    let mut extras = InstanceExtras {
+                        created: Some(SystemTime::now()),
+                        ..Default::default()
+                    };
+                    if let Some(notes) = read_text_from_zip(&file_path, BRIMPKG_NOTES_FILE) {
+                        extras.notes = notes;
+                    }
+                    let name = instance.metadata.name.clone();
+                    cli_config.add_extras(&name, extras)?;
+                    config.add_instance(instance);
+                    println!("Instance {} added!", name);
                 }
             }
             0 => {
-                let index = instance_selection(config, indexes)?;
-                if let Some(index) = index {
-                    if let Some(save_path) =
+                let index = instance_selection(config, cli_config, indexes)?;
+                if let Some(index) = index
+                    && let Some(save_path) =
                         select_file("Choose Folder to save the instance in", true, None, None)?
+                {
+                    let save_file_path = save_path.join(format!(
+                        "{}.brimpkg",
+                        safe_file_name(&config.instances[index].metadata.name)
+                    ));
+                    println!(
+                        "Saving instance {} to {}",
+                        &config.instances[index].metadata.name,
+                        save_file_path.to_string_lossy()
+                    );
+                    let saved = config.instances[index].save_brimpkg(
+                        &save_file_path,
+                        Confirm::new()
+                            .with_prompt("Do you want to transfer your saves?")
+                            .interact()?,
+                        Confirm::new()
+                            .with_prompt("Do you want to transfer your playtime data?")
+                            .interact()?,
+                    );
+                    if let Err(error) = saved {
+                        println!("Could not save the instance: {}", error);
+                        continue;
+                    }
+                    let extras = cli_config.extras(&config.instances[index].metadata.name);
+                    if !extras.notes.is_empty()
+                        && let Err(error) =
+                            add_text_to_zip(&save_file_path, BRIMPKG_NOTES_FILE, &extras.notes)
                     {
-                        let save_file_path = save_path.join(format!(
-                            "{}.brimpkg",
-                            &config.instances[index].metadata.name
-                        ));
                         println!(
-                            "Saving instance {} to {}",
-                            &config.instances[index].metadata.name,
-                            save_file_path.to_string_lossy()
+                            "Instance saved, but its notes could not be added: {}",
+                            error
                         );
-                        let _ = config.instances[index].save_brimpkg(
-                            &save_file_path,
-                            Confirm::with_theme(&ColorfulTheme::default())
-                                .with_prompt("Do you want to transfer your saves?")
-                                .interact()?,
-                            Confirm::with_theme(&ColorfulTheme::default())
-                                .with_prompt("Do you want to transfer your playtime data?")
-                                .interact()?,
-                        );
-                        println!("Instance saved!");
+                        continue;
                     }
+                    println!("Instance saved!");
                 }
             }
             2 => return Ok(()),
@@ -237,3 +1130,98 @@ pub fn import_export(config: &mut SulphurConfig, indexes: &[usize]) -> Result<()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_config::SessionRecord;
+    use std::time::Duration;
+
+    fn at(minutes: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(minutes * 60)
+    }
+
+    fn session(started: u64, minutes: u64) -> SessionRecord {
+        SessionRecord {
+            started_at: at(started),
+            duration: Duration::from_secs(minutes * 60),
+            raw_duration: None,
+            exit_code: Some(0),
+            map: None,
+        }
+    }
+
+    fn instance(last_played: u64, last_minutes: u64) -> Instance {
+        Instance {
+            metadata: Metadata {
+                name: "a".to_string(),
+                image: None,
+                playtime: Duration::ZERO,
+                last_played: Some(at(last_played)),
+                last_session_duration: Some(Duration::from_secs(last_minutes * 60)),
+            },
+            gamedata: GameData {
+                iwads: vec![],
+                mods: vec![],
+                savedir: Default::default(),
+                additional_params: vec![],
+            },
+        }
+    }
+
+    fn extras(sessions: Vec<SessionRecord>) -> InstanceExtras {
+        InstanceExtras {
+            sessions,
+            ..Default::default()
+        }
+    }
+
+    fn last(instance: &Instance) -> (Option<SystemTime>, Option<Duration>) {
+        (
+            instance.metadata.last_played,
+            instance.metadata.last_session_duration,
+        )
+    }
+
+    #[test]
+    fn deleting_the_last_session_falls_back_to_the_previous_one() {
+        let mut instance = instance(100, 30);
+        sync_last_session(&mut instance, &extras(vec![session(50, 10)]), at(100));
+        assert_eq!(
+            last(&instance),
+            (Some(at(50)), Some(Duration::from_secs(600)))
+        );
+    }
+
+    #[test]
+    fn deleting_the_only_session_keeps_the_metadata() {
+        let mut instance = instance(100, 30);
+        sync_last_session(&mut instance, &extras(vec![]), at(100));
+        assert_eq!(
+            last(&instance),
+            (Some(at(100)), Some(Duration::from_secs(1800)))
+        );
+    }
+
+    #[test]
+    fn metadata_newer_than_the_log_is_kept() {
+        // Played before sessions were logged, or the log was trimmed.
+        let mut instance = instance(200, 30);
+        sync_last_session(&mut instance, &extras(vec![session(50, 10)]), at(100));
+        assert_eq!(
+            last(&instance),
+            (Some(at(200)), Some(Duration::from_secs(1800)))
+        );
+    }
+
+    #[test]
+    fn shortening_the_last_session_updates_its_length() {
+        let mut instance = instance(100, 30);
+        let sessions = vec![session(50, 10), session(100, 20)];
+        sync_last_session(&mut instance, &extras(sessions), at(100));
+        assert_eq!(
+            last(&instance),
+            (Some(at(100)), Some(Duration::from_secs(1200)))
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::{bail, Result};
+use std::ffi::OsString;
+use std::process::{Child, Command, ExitStatus};
+use std::time::{Duration, Instant, SystemTime};
+use sulphur_core::Instance;
+
+use crate::cli_config::{CliSettings, IdlePolicy, InstanceExtras, SessionRecord};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct Session {
+    /// Playtime counted for the session after applying the idle policy.
+    pub duration: Duration,
+    pub raw_duration: Duration,
+    pub exit_status: ExitStatus,
+}
+
+/// Runs the global and then the instance's pre-launch hooks, stopping at the
+/// first one that fails.
+pub fn run_pre_launch_hooks(
+    instance: &Instance,
+    extras: &InstanceExtras,
+    settings: &CliSettings,
+) -> Result<()> {
+    let pre_launch_hooks = settings
+        .hooks
+        .pre_launch
+        .iter()
+        .chain(&extras.hooks.pre_launch);
+    for hook in pre_launch_hooks {
+        let status = hook_command(hook, instance).status()?;
+        if !status.success() {
+            bail!("pre-launch hook '{}' failed ({})", hook, status);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the engine and then the post-exit hooks, recording the session in the
+/// instance's metadata and session log. The pre-launch hooks must have been run
+/// with [`run_pre_launch_hooks`] first.
+pub fn launch(
+    instance: &mut Instance,
+    extras: &mut InstanceExtras,
+    settings: &CliSettings,
+    argv: Vec<OsString>,
+) -> Result<Session> {
+    let Some((program, args)) = argv.split_first() else {
+        bail!("no engine command configured");
+    };
+
+    let started_at = SystemTime::now();
+    let child = Command::new(program).args(args).spawn()?;
+    let session = wait_for_session(child, &settings.idle_policy)?;
+
+    instance.metadata.playtime += session.duration;
+    instance.metadata.last_played = Some(started_at);
+    instance.metadata.last_session_duration = Some(session.duration);
+    extras.sessions.push(SessionRecord {
+        started_at,
+        duration: session.duration,
+        raw_duration: Some(session.raw_duration),
+        exit_code: session.exit_status.code(),
+        map: map_from_argv(&argv),
+    });
+
+    let post_exit_hooks = settings
+        .hooks
+        .post_exit
+        .iter()
+        .chain(&extras.hooks.post_exit);
+    for hook in post_exit_hooks {
+        let status = hook_command(hook, instance)
+            .env(
+                "SULPHUR_EXIT_CODE",
+                session
+                    .exit_status
+                    .code()
+                    .map(|code| code.to_string())
+                    .unwrap_or_default(),
+            )
+            .env(
+                "SULPHUR_SESSION_SECONDS",
+                session.duration.as_secs().to_string(),
+            )
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => println!("Warning: post-exit hook '{}' failed ({})", hook, status),
+            Err(error) => println!(
+                "Warning: post-exit hook '{}' could not run: {}",
+                hook, error
+            ),
+        }
+    }
+
+    Ok(session)
+}
+
+/// Waits for the engine to exit, keeping track of how long it was stopped.
+fn wait_for_session(mut child: Child, policy: &IdlePolicy) -> Result<Session> {
+    let timer = Instant::now();
+    let mut stopped = Duration::ZERO;
+
+    let exit_status = if policy.exclude_stopped {
+        loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+            if is_stopped(child.id()) {
+                stopped += POLL_INTERVAL;
+            }
+        }
+    } else {
+        child.wait()?
+    };
+
+    let raw_duration = timer.elapsed();
+    let mut duration = raw_duration.saturating_sub(stopped);
+    if let Some(max_session) = policy.max_session {
+        duration = duration.min(max_session);
+    }
+    Ok(Session {
+        duration,
+        raw_duration,
+        exit_status,
+    })
+}
+
+/// Whether the process is currently stopped, according to `/proc`. Always
+/// false where `/proc` isn't available.
+fn is_stopped(pid: u32) -> bool {
+    let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) else {
+        return false;
+    };
+    // The state follows the parenthesised command name, which may itself
+    // contain spaces or parentheses.
+    stat.rsplit_once(')')
+        .and_then(|(_, rest)| rest.split_whitespace().next())
+        .is_some_and(|state| state == "T")
+}
+
+/// The starting map passed with `+map <name>` or `-warp [episode] <map>`.
+fn map_from_argv(argv: &[OsString]) -> Option<String> {
+    let args: Vec<String> = argv
+        .iter()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+    let position = args
+        .iter()
+        .rposition(|arg| arg.eq_ignore_ascii_case("+map") || arg.eq_ignore_ascii_case("-warp"))?;
+    let values: Vec<&String> = args[position + 1..]
+        .iter()
+        .take_while(|arg| !arg.starts_with('-') && !arg.starts_with('+'))
+        .collect();
+
+    if args[position].eq_ignore_ascii_case("+map") {
+        return values.first().map(|map| map.to_uppercase());
+    }
+    match values.as_slice() {
+        [episode, map] => Some(format!("E{}M{}", episode, map)),
+        [map] => Some(match map.parse::<u32>() {
+            Ok(number) => format!("MAP{:02}", number),
+            Err(_) => map.to_uppercase(),
+        }),
+        _ => None,
+    }
+}
+
+fn hook_command(hook: &str, instance: &Instance) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(hook)
+        .env("SULPHUR_INSTANCE_NAME", &instance.metadata.name)
+        .env("SULPHUR_SAVE_DIR", &instance.gamedata.savedir);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(args: &[&str]) -> Option<String> {
+        let argv: Vec<OsString> = args.iter().map(OsString::from).collect();
+        map_from_argv(&argv)
+    }
+
+    #[test]
+    fn reads_the_map_from_the_command_line() {
+        assert_eq!(map(&["gzdoom", "+map", "map07"]).as_deref(), Some("MAP07"));
+        assert_eq!(map(&["gzdoom", "+MAP", "e2m4"]).as_deref(), Some("E2M4"));
+        assert_eq!(map(&["gzdoom", "-warp", "1", "2"]).as_deref(), Some("E1M2"));
+        assert_eq!(
+            map(&["gzdoom", "-warp", "7", "-skill", "4"]).as_deref(),
+            Some("MAP07")
+        );
+        assert_eq!(map(&["gzdoom", "-warp", "start"]).as_deref(), Some("START"));
+    }
+
+    #[test]
+    fn last_map_argument_wins() {
+        assert_eq!(
+            map(&["+map", "MAP01", "-warp", "3"]).as_deref(),
+            Some("MAP03")
+        );
+        assert_eq!(
+            map(&["-warp", "3", "+map", "MAP01"]).as_deref(),
+            Some("MAP01")
+        );
+    }
+
+    #[test]
+    fn no_map_without_a_value() {
+        assert_eq!(map(&["gzdoom", "-file", "mod.pk3"]), None);
+        assert_eq!(map(&["gzdoom", "-warp"]), None);
+        assert_eq!(map(&["gzdoom", "-warp", "-skill", "4"]), None);
+        assert_eq!(map(&["gzdoom", "+map", "+skill"]), None);
+        assert_eq!(map(&["gzdoom", "-warp", "1", "2", "3"]), None);
+    }
+}
@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::time::SystemTime;
+use sulphur_core::{Instance, SulphurConfig};
+
+use crate::asset_management::{enabled_iwads, enabled_mods};
+use crate::cli_config::{CliConfig, InstanceExtras};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum SortKey {
+    #[default]
+    LastPlayed,
+    Playtime,
+    Name,
+    Created,
+    Iwad,
+    ModCount,
+    /// The order of `SulphurConfig::instances`, arranged by hand.
+    Custom,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 7] = [
+        SortKey::LastPlayed,
+        SortKey::Playtime,
+        SortKey::Name,
+        SortKey::Created,
+        SortKey::Iwad,
+        SortKey::ModCount,
+        SortKey::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortKey::LastPlayed => "Last Played",
+            SortKey::Playtime => "Playtime",
+            SortKey::Name => "Name",
+            SortKey::Created => "Creation Date",
+            SortKey::Iwad => "IWAD",
+            SortKey::ModCount => "Number of Mods",
+            SortKey::Custom => "Custom Order",
+        }
+    }
+
+    /// Whether this key reads best descending: most recent or largest first.
+    pub fn descending_by_default(&self) -> bool {
+        matches!(
+            self,
+            SortKey::LastPlayed | SortKey::Playtime | SortKey::Created | SortKey::ModCount
+        )
+    }
+
+    fn compare(
+        &self,
+        (a, a_extras): (&Instance, Option<&InstanceExtras>),
+        (b, b_extras): (&Instance, Option<&InstanceExtras>),
+    ) -> Ordering {
+        match self {
+            // Unplayed instances count as the most recent, as they are the
+            // ones most likely to be played next.
+            SortKey::LastPlayed => match (a.metadata.last_played, b.metadata.last_played) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(&b),
+            },
+            SortKey::Playtime => a.metadata.playtime.cmp(&b.metadata.playtime),
+            SortKey::Name => a
+                .metadata
+                .name
+                .to_lowercase()
+                .cmp(&b.metadata.name.to_lowercase()),
+            SortKey::Created => {
+                let created = |extras: Option<&InstanceExtras>| -> SystemTime {
+                    extras
+                        .and_then(|extras| extras.created)
+                        .unwrap_or(SystemTime::UNIX_EPOCH)
+                };
+                created(a_extras).cmp(&created(b_extras))
+            }
+            SortKey::Iwad => first_iwad(a).cmp(&first_iwad(b)),
+            SortKey::ModCount => mod_count(a).cmp(&mod_count(b)),
+            // The sort is stable, leaving instances in config order.
+            SortKey::Custom => Ordering::Equal,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+pub enum InstanceFilter {
+    #[default]
+    All,
+    Unplayed,
+    Tag(String),
+    Group(String),
+    /// IWAD file name.
+    Iwad(String),
+    /// Engine command, empty for instances using the global one.
+    Engine(String),
+}
+
+impl InstanceFilter {
+    fn matches(&self, instance: &Instance, extras: Option<&InstanceExtras>) -> bool {
+        match self {
+            InstanceFilter::All => true,
+            InstanceFilter::Unplayed => instance.metadata.last_played.is_none(),
+            InstanceFilter::Tag(tag) => extras.is_some_and(|extras| extras.tags.contains(tag)),
+            InstanceFilter::Group(group) => {
+                extras.is_some_and(|extras| extras.group.as_ref() == Some(group))
+            }
+            InstanceFilter::Iwad(iwad) => iwad_names(instance).contains(iwad),
+            InstanceFilter::Engine(engine) => {
+                let instance_engine = extras.and_then(|extras| extras.engine.as_deref());
+                instance_engine.unwrap_or_default() == engine.as_str()
+            }
+        }
+    }
+}
+
+/// How the main instance list is sorted and filtered, kept between runs.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ListView {
+    pub sort: SortKey,
+    pub descending: bool,
+    pub filter: InstanceFilter,
+}
+
+impl Default for ListView {
+    fn default() -> Self {
+        Self {
+            sort: SortKey::default(),
+            descending: SortKey::default().descending_by_default(),
+            filter: InstanceFilter::default(),
+        }
+    }
+}
+
+impl ListView {
+    /// Indexes of the instances to list: archived ones and those not matching
+    /// the filter are left out, favourites come first.
+    pub fn instance_order(&self, config: &SulphurConfig, cli_config: &CliConfig) -> Vec<usize> {
+        let entry = |index: usize| {
+            let instance = &config.instances[index];
+            (instance, cli_config.instances.get(&instance.metadata.name))
+        };
+
+        let mut order: Vec<usize> = (0..config.instances.len())
+            .filter(|&index| {
+                let (instance, extras) = entry(index);
+                !extras.is_some_and(|extras| extras.archived)
+                    && self.filter.matches(instance, extras)
+            })
+            .collect();
+
+        // Stable, so ties keep the order they were added in.
+        order.sort_by(|&a, &b| {
+            let ordering = self.sort.compare(entry(a), entry(b));
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        order.sort_by_key(|&index| !entry(index).1.is_some_and(|extras| extras.favourite));
+        order
+    }
+}
+
+/// File names of an instance's enabled IWADs.
+pub fn iwad_names(instance: &Instance) -> Vec<String> {
+    enabled_iwads(instance)
+        .filter_map(|asset| asset.path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect()
+}
+
+fn first_iwad(instance: &Instance) -> String {
+    iwad_names(instance)
+        .first()
+        .map(|name| name.to_lowercase())
+        .unwrap_or_default()
+}
+
+fn mod_count(instance: &Instance) -> usize {
+    enabled_mods(instance).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use sulphur_core::{GameData, Metadata};
+
+    fn instance(name: &str, playtime_minutes: u64) -> Instance {
+        Instance {
+            metadata: Metadata {
+                name: name.to_string(),
+                image: None,
+                playtime: Duration::from_secs(playtime_minutes * 60),
+                last_played: None,
+                last_session_duration: None,
+            },
+            gamedata: GameData {
+                iwads: vec![],
+                mods: vec![],
+                savedir: Default::default(),
+                additional_params: vec![],
+            },
+        }
+    }
+
+    /// Instances `a` to `e` with increasing playtime; `b` and `d` are
+    /// favourites and `c` is archived.
+    fn configs() -> (SulphurConfig, CliConfig) {
+        let mut config = SulphurConfig::new();
+        let mut cli_config = CliConfig::default();
+        for (playtime, name) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            config.instances.push(instance(name, playtime as u64));
+            let extras = InstanceExtras {
+                favourite: name == "b" || name == "d",
+                archived: name == "c",
+                ..Default::default()
+            };
+            cli_config.instances.insert(name.to_string(), extras);
+        }
+        (config, cli_config)
+    }
+
+    fn names(config: &SulphurConfig, order: &[usize]) -> Vec<String> {
+        order
+            .iter()
+            .map(|&index| config.instances[index].metadata.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn favourites_first_and_archived_hidden() {
+        let (config, cli_config) = configs();
+        let view = ListView {
+            sort: SortKey::Playtime,
+            descending: true,
+            filter: InstanceFilter::All,
+        };
+        let order = view.instance_order(&config, &cli_config);
+        assert_eq!(names(&config, &order), ["d", "b", "e", "a"]);
+    }
+
+    #[test]
+    fn ascending_sort_keeps_favourites_pinned() {
+        let (config, cli_config) = configs();
+        let view = ListView {
+            sort: SortKey::Playtime,
+            descending: false,
+            filter: InstanceFilter::All,
+        };
+        let order = view.instance_order(&config, &cli_config);
+        assert_eq!(names(&config, &order), ["b", "d", "a", "e"]);
+    }
+
+    #[test]
+    fn custom_order_is_config_order() {
+        let (config, cli_config) = configs();
+        let view = ListView {
+            sort: SortKey::Custom,
+            descending: false,
+            filter: InstanceFilter::All,
+        };
+        let order = view.instance_order(&config, &cli_config);
+        assert_eq!(names(&config, &order), ["b", "d", "a", "e"]);
+    }
+
+    #[test]
+    fn filter_applies_before_sorting() {
+        let (mut config, cli_config) = configs();
+        config.instances[4].metadata.last_played = Some(SystemTime::now());
+        let view = ListView {
+            sort: SortKey::Name,
+            descending: false,
+            filter: InstanceFilter::Unplayed,
+        };
+        let order = view.instance_order(&config, &cli_config);
+        assert_eq!(names(&config, &order), ["b", "d", "a"]);
+    }
+}
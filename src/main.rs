@@ -1,7 +1,11 @@
 extern crate chrono;
 
-use anyhow::Result;
-use sulphur_core::{SaveableDefaultPath, SulphurConfig};
+use anyhow::{bail, Result};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use crate::input::{ScriptedInput, Select};
+use crate::profile::{profile_names, requested_location, ConfigLocation};
 
 mod ui;
 mod menu;
@@ -9,8 +13,140 @@ mod file_utils;
 mod instance_management;
 mod asset_management;
 mod duration_utils;
+mod cli_config;
+mod command;
+mod launch;
+mod template_management;
+mod wad_utils;
+mod image_utils;
+mod instance_diff;
+mod stats;
+mod time_utils;
+mod list_view;
+mod input;
+mod profile;
+
+const USAGE: &str = "\
+Usage:
+  sulphur_cli                     open the interactive menus
+  sulphur_cli <instance name>     launch an instance without any prompts
+  sulphur_cli --list              print the names of all instances
+  sulphur_cli --script <file>     answer the menus from a file, or - for stdin
+  sulphur_cli --help              show this message
+
+Options:
+  --config <file>    use this config file instead of Sulphur's default one,
+                     also read from $SULPHUR_CONFIG
+  --profile <name>   use a profile created in the global settings";
 
 fn main() -> Result<()> {
-    let mut config = SulphurConfig::load().unwrap_or_else(|_| SulphurConfig::new());
-    ui::run_main_loop(&mut config)
+    let mut list = false;
+    let mut scripted = false;
+    let mut instance_name: Option<String> = None;
+    let mut config_file: Option<PathBuf> = None;
+    let mut profile: Option<String> = None;
+
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--help" | "-h") => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            Some("--list") => list = true,
+            Some("--script") => {
+                let Some(path) = args.next() else {
+                    bail!("--script needs a file to read answers from, or - for stdin");
+                };
+                input::install(Box::new(ScriptedInput::open(Path::new(&path))?));
+                scripted = true;
+            }
+            Some("--config") => {
+                let Some(path) = args.next() else {
+                    bail!("--config needs a config file");
+                };
+                config_file = Some(path.into());
+            }
+            Some("--profile") => {
+                let Some(name) = args.next() else {
+                    bail!("--profile needs the name of a profile");
+                };
+                profile = Some(name.to_string_lossy().to_string());
+            }
+            Some(flag) if flag.starts_with('-') => {
+                bail!("unknown option '{}'\n\n{}", flag, USAGE)
+            }
+            _ if instance_name.is_none() => {
+                instance_name = Some(arg.to_string_lossy().to_string())
+            }
+            _ => bail!("only one instance can be launched at a time\n\n{}", USAGE),
+        }
+    }
+
+    if config_file.is_some() && profile.is_some() {
+        bail!("--config and --profile can't be used together");
+    }
+    let interactive = !list && instance_name.is_none();
+    if interactive && !scripted && !std::io::stdin().is_terminal() {
+        eprintln!(
+            "Sulphur's menus need an interactive terminal, but stdin isn't one.\n\n{}",
+            USAGE
+        );
+        std::process::exit(2);
+    }
+
+    let mut location = match requested_location(config_file, profile)? {
+        Some(location) => location,
+        None if interactive => choose_profile()?,
+        None => ConfigLocation::Default,
+    };
+    let (mut config, mut cli_config) = location.load()?;
+
+    if list {
+        for instance in &config.instances {
+            println!("{}", instance.metadata.name);
+        }
+        return Ok(());
+    }
+
+    if let Some(name) = instance_name {
+        let Some(index) = config
+            .instances
+            .iter()
+            .position(|instance| instance.metadata.name == name)
+        else {
+            bail!("there is no instance named '{}', see --list", name);
+        };
+        let session = ui::launch_instance(&mut config, &mut cli_config, index, false)?;
+        location.save(&config, &cli_config)?;
+        // Pass a failing engine's exit code on to whatever started us.
+        let failure = session
+            .and_then(|session| session.exit_status.code())
+            .filter(|&code| code != 0);
+        if let Some(code) = failure {
+            std::process::exit(code);
+        }
+        return Ok(());
+    }
+
+    ui::run_main_loop(&mut location, &mut config, &mut cli_config)
+}
+
+/// Asks which profile to open when there are any besides the default one.
+fn choose_profile() -> Result<ConfigLocation> {
+    let names = profile_names()?;
+    if names.is_empty() {
+        return Ok(ConfigLocation::Default);
+    }
+    let mut items = vec![ConfigLocation::Default.name()];
+    items.extend(names.iter().cloned());
+    let selection = Select::new()
+        .with_prompt("Profile")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    Ok(match selection {
+        0 => ConfigLocation::Default,
+        index => ConfigLocation::Profile(names[index - 1].clone()),
+    })
 }
\ No newline at end of file
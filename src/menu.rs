@@ -1,13 +1,26 @@
 pub const BACK_BUTTON: &str = "Back";
 
+#[allow(clippy::enum_variant_names)]
 pub enum Menu {
     MainMenu,
     EditInstanceMenu,
     IwadManagementMenu,
     ModManagementMenu,
+    PatchManagementMenu,
     GlobalSettingsMenu,
     AdditionalParamsMenu,
+    LaunchEnvironmentMenu,
+    HooksMenu,
     ImportExportMenu,
+    FullCommandMenu,
+    TemplatesMenu,
+    OrganiseMenu,
+    FilterMenu,
+    ImageMenu,
+    ArchivedInstanceMenu,
+    StatisticsMenu,
+    PlaytimeMenu,
+    ArrangeMenu,
 }
 
 impl Menu {
@@ -16,9 +29,15 @@ impl Menu {
             Menu::MainMenu => &[
                 "Run Instance",
                 "Change List Order",
+                "Filter Instances",
                 "Create New Instance",
+                "Duplicate Instance",
+                "Templates",
                 "Edit Instance",
+                "Compare Instances",
                 "Import/Export Instance",
+                "Archived Instances",
+                "Statistics",
                 "Configure Global Settings",
                 "Save & Exit",
             ],
@@ -26,18 +45,106 @@ impl Menu {
                 "Edit Name",
                 "Edit IWADs",
                 "Edit Mods",
+                "Edit DeHackEd Patches",
                 "Edit Save Directory",
                 "Edit Additional Parameters",
+                "Edit Engine",
+                "Edit Wrappers & Environment",
+                "Edit Hooks",
+                "Edit Tags, Group & Favourite",
+                "Edit Notes",
+                "Set Image",
+                "Show Details",
+                "History & Revert",
+                "Adjust Playtime & Sessions",
                 "See Full Command",
+                "Archive Instance",
                 "Remove Instance",
                 "Save Changes",
                 BACK_BUTTON,
             ],
             Menu::IwadManagementMenu => &["Add IWAD", "Toggle IWAD", "Remove IWAD", BACK_BUTTON],
             Menu::ModManagementMenu => &["Add Mod", "Toggle Mod", "Remove Mod", BACK_BUTTON],
-            Menu::GlobalSettingsMenu => &["Set GZDoom Path", BACK_BUTTON],
+            Menu::PatchManagementMenu => {
+                &["Add Patch", "Toggle Patch", "Remove Patch", BACK_BUTTON]
+            }
+            Menu::GlobalSettingsMenu => &[
+                "Set GZDoom Path",
+                "Toggle Loading Patches with -file",
+                "Edit Wrappers & Environment",
+                "Edit Hooks",
+                "Set Compact List Threshold",
+                "Set Image Display Mode",
+                "Set Idle Policy",
+                "Set Duration Style",
+                "Set Date & Time Display",
+                "Switch Profile",
+                BACK_BUTTON,
+            ],
             Menu::AdditionalParamsMenu => &["Add New Parameter", "Remove Parameter", BACK_BUTTON],
+            Menu::LaunchEnvironmentMenu => &[
+                "Add Wrapper",
+                "Remove Wrapper",
+                "Set Environment Variable",
+                "Remove Environment Variable",
+                BACK_BUTTON,
+            ],
+            Menu::HooksMenu => &[
+                "Add Pre-Launch Hook",
+                "Add Post-Exit Hook",
+                "Remove Hook",
+                BACK_BUTTON,
+            ],
             Menu::ImportExportMenu => &["Export as .brimpkg", "Import .brimpkg", BACK_BUTTON],
+            Menu::FullCommandMenu => &["Copy to Clipboard", "Write Shell Script", BACK_BUTTON],
+            Menu::TemplatesMenu => &[
+                "Create Instance from Template",
+                "Save Instance as Template",
+                "Remove Template",
+                BACK_BUTTON,
+            ],
+            Menu::OrganiseMenu => &[
+                "Toggle Favourite",
+                "Add Tag",
+                "Remove Tag",
+                "Set Group",
+                BACK_BUTTON,
+            ],
+            Menu::FilterMenu => &[
+                "Show All",
+                "Unplayed Only",
+                "Filter by Tag",
+                "Filter by Group",
+                "Filter by IWAD",
+                "Filter by Engine",
+            ],
+            Menu::ImageMenu => &[
+                "Choose Image File",
+                "Extract TITLEPIC from WADs",
+                "Remove Image",
+                BACK_BUTTON,
+            ],
+            Menu::ArchivedInstanceMenu => &["Restore Instance", "Delete Permanently", BACK_BUTTON],
+            Menu::StatisticsMenu => &[
+                "Daily Totals",
+                "Weekly Totals",
+                "Monthly Totals",
+                BACK_BUTTON,
+            ],
+            Menu::ArrangeMenu => &[
+                "Move Up",
+                "Move Down",
+                "Move to Top",
+                "Move to Bottom",
+                BACK_BUTTON,
+            ],
+            Menu::PlaytimeMenu => &[
+                "Add Playtime",
+                "Subtract Playtime",
+                "Delete Session",
+                "Trim Session",
+                BACK_BUTTON,
+            ],
         }
     }
 }
@@ -0,0 +1,172 @@
+use anyhow::{anyhow, bail, Result};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use sulphur_core::{SaveableDefaultPath, SulphurConfig};
+
+use crate::cli_config::{config_dir, config_path, CliConfig};
+
+const PROFILES_DIR: &str = "profiles";
+const CONFIG_EXTENSION: &str = "toml";
+/// Extension of the CLI config kept next to a config file given by path.
+const CLI_CONFIG_EXTENSION: &str = "cli.toml";
+const CONFIG_ENV_VAR: &str = "SULPHUR_CONFIG";
+
+/// Where the core config and the CLI config belonging to it are read from
+/// and saved to.
+#[derive(Clone, PartialEq)]
+pub enum ConfigLocation {
+    /// Sulphur's own config, shared with the other frontends.
+    Default,
+    /// A named profile, kept in the `profiles` config directory.
+    Profile(String),
+    /// A config file given with `--config` or `SULPHUR_CONFIG`.
+    File(PathBuf),
+}
+
+impl ConfigLocation {
+    pub fn name(&self) -> String {
+        match self {
+            ConfigLocation::Default => "Default".to_string(),
+            ConfigLocation::Profile(name) => name.clone(),
+            ConfigLocation::File(path) => path.display().to_string(),
+        }
+    }
+
+    /// Path of the core config, `None` for the one managed by sulphur_core.
+    fn core_path(&self) -> Result<Option<PathBuf>> {
+        Ok(match self {
+            ConfigLocation::Default => None,
+            ConfigLocation::Profile(name) => Some(profile_path(name)?),
+            ConfigLocation::File(path) => Some(path.clone()),
+        })
+    }
+
+    fn cli_path(&self) -> Result<PathBuf> {
+        match self.core_path()? {
+            Some(path) => Ok(path.with_extension(CLI_CONFIG_EXTENSION)),
+            None => config_path(),
+        }
+    }
+
+    /// Reads both configs. Missing files give empty configs, but files that
+    /// can't be read are an error rather than being replaced on the next save.
+    pub fn load(&self) -> Result<(SulphurConfig, CliConfig)> {
+        let config = match self.core_path()? {
+            Some(path) => load_config_file(&path)?,
+            None => load_default_config()?,
+        };
+        let cli_path = self.cli_path()?;
+        let cli_config = CliConfig::load(&cli_path)
+            .map_err(|error| anyhow!("couldn't read '{}': {}", cli_path.display(), error))?;
+        Ok((config, cli_config))
+    }
+
+    pub fn save(&self, config: &SulphurConfig, cli_config: &CliConfig) -> Result<()> {
+        match self.core_path()? {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, toml::to_string(config)?)?;
+            }
+            None => config.save()?,
+        }
+        cli_config.save(&self.cli_path()?)
+    }
+}
+
+fn load_config_file(path: &Path) -> Result<SulphurConfig> {
+    if !path.exists() {
+        return Ok(SulphurConfig::new());
+    }
+    let text = std::fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|error| anyhow!("couldn't read '{}': {}", path.display(), error))
+}
+
+fn load_default_config() -> Result<SulphurConfig> {
+    match SulphurConfig::load() {
+        Ok(config) => Ok(config),
+        Err(error) => {
+            let missing = error.chain().any(|cause| {
+                cause
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|error| error.kind() == ErrorKind::NotFound)
+            });
+            if missing {
+                Ok(SulphurConfig::new())
+            } else {
+                Err(error.context("couldn't read Sulphur's config, fix or move it aside first"))
+            }
+        }
+    }
+}
+
+fn profiles_dir() -> Result<PathBuf> {
+    Ok(config_dir()?.join(PROFILES_DIR))
+}
+
+fn profile_path(name: &str) -> Result<PathBuf> {
+    Ok(profiles_dir()?.join(name).with_extension(CONFIG_EXTENSION))
+}
+
+/// Names of the existing profiles, sorted.
+pub fn profile_names() -> Result<Vec<String>> {
+    let dir = profiles_dir()?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut names: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|file_name| !file_name.ends_with(&format!(".{}", CLI_CONFIG_EXTENSION)))
+        .filter_map(|file_name| {
+            file_name
+                .strip_suffix(&format!(".{}", CONFIG_EXTENSION))
+                .map(str::to_string)
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Every config besides `current`: the default one and each profile. Config
+/// files given by path aren't known from here and so aren't included.
+pub fn other_configs(current: &ConfigLocation) -> Result<Vec<(SulphurConfig, CliConfig)>> {
+    let mut locations = vec![ConfigLocation::Default];
+    locations.extend(profile_names()?.into_iter().map(ConfigLocation::Profile));
+    locations
+        .into_iter()
+        .filter(|location| location != current)
+        .map(|location| location.load())
+        .collect()
+}
+
+/// Profile names end up in file names, so only plain characters are allowed.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The location given by the command line or the environment, if any.
+pub fn requested_location(
+    config: Option<PathBuf>,
+    profile: Option<String>,
+) -> Result<Option<ConfigLocation>> {
+    if let Some(name) = profile {
+        if !profile_names()?.contains(&name) {
+            bail!(
+                "there is no profile named '{}', create it from the global settings first",
+                name
+            );
+        }
+        return Ok(Some(ConfigLocation::Profile(name)));
+    }
+    let from_env = || {
+        std::env::var_os(CONFIG_ENV_VAR)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    };
+    Ok(config.or_else(from_env).map(ConfigLocation::File))
+}
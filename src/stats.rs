@@ -0,0 +1,302 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate};
+use console::Style;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use sulphur_core::SulphurConfig;
+use tabled::{builder::Builder, settings::Style as TabledStyle};
+
+use crate::cli_config::{CliConfig, SessionRecord};
+use crate::duration_utils::DurationStyle;
+use crate::input::Select;
+use crate::menu::Menu;
+
+const BAR_WIDTH: usize = 40;
+const TOP_COUNT: usize = 5;
+
+/// A recorded session together with the instance it belongs to.
+struct Entry<'a> {
+    instance: &'a str,
+    session: &'a SessionRecord,
+}
+
+impl Entry<'_> {
+    fn date(&self) -> NaiveDate {
+        DateTime::<Local>::from(self.session.started_at).date_naive()
+    }
+}
+
+pub fn statistics(config: &SulphurConfig, cli_config: &CliConfig) -> Result<()> {
+    let entries: Vec<Entry> = config
+        .instances
+        .iter()
+        .filter_map(|instance| {
+            let extras = cli_config.instances.get(&instance.metadata.name)?;
+            Some(extras.sessions.iter().map(|session| Entry {
+                instance: &instance.metadata.name,
+                session,
+            }))
+        })
+        .flatten()
+        .collect();
+
+    if entries.is_empty() {
+        println!("No sessions recorded yet!");
+        return Ok(());
+    }
+
+    let style = cli_config.settings.duration_style;
+    print_summary(config, &entries, style);
+
+    let today = Local::now().date_naive();
+    loop {
+        let rows = match Select::new()
+            .with_prompt("Show Totals")
+            .items(Menu::StatisticsMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => totals_by(
+                &entries,
+                today - Days::new(13),
+                |date| date,
+                |date| date.format("%a %Y-%m-%d").to_string(),
+            ),
+            1 => totals_by(
+                &entries,
+                today - Days::new(11 * 7),
+                |date| date - Days::new(date.weekday().num_days_from_monday() as u64),
+                |date| format!("Week of {}", date),
+            ),
+            2 => totals_by(
+                &entries,
+                today - Months::new(11),
+                |date| date.with_day(1).unwrap(),
+                |date| date.format("%B %Y").to_string(),
+            ),
+            _ => return Ok(()),
+        };
+        print_bar_chart(&rows, style);
+    }
+}
+
+fn print_summary(config: &SulphurConfig, entries: &[Entry], style: DurationStyle) {
+    let title_style = Style::new().bold().underlined();
+
+    let total: Duration = entries.iter().map(|entry| entry.session.duration).sum();
+    let days: BTreeSet<NaiveDate> = entries.iter().map(Entry::date).collect();
+    let (current_streak, longest_streak) = streaks(&days, Local::now().date_naive());
+
+    let data = [
+        ("Recorded Playtime", style.format(total)),
+        ("Sessions", entries.len().to_string()),
+        ("Days Played", days.len().to_string()),
+        ("Current Streak", format!("{} days", current_streak)),
+        ("Longest Streak", format!("{} days", longest_streak)),
+    ];
+    let mut table = Builder::new();
+    for (name, value) in data.iter() {
+        table.push_record([name, value.as_str()]);
+    }
+    let mut table = table.build();
+    table.with(TabledStyle::extended());
+    println!("{}", table);
+
+    println!("{}", title_style.apply_to("Most Played"));
+    let mut instances: Vec<_> = config.instances.iter().collect();
+    instances.sort_by_key(|instance| std::cmp::Reverse(instance.metadata.playtime));
+    for instance in instances.iter().take(TOP_COUNT) {
+        println!(
+            "  {}  {}",
+            style.format(instance.metadata.playtime),
+            instance.metadata.name
+        );
+    }
+
+    println!("{}", title_style.apply_to("Longest Sessions"));
+    let mut longest: Vec<&Entry> = entries.iter().collect();
+    longest.sort_by_key(|entry| std::cmp::Reverse(entry.session.duration));
+    for entry in longest.iter().take(TOP_COUNT) {
+        let map = match &entry.session.map {
+            Some(map) => format!(" ({})", map),
+            None => String::new(),
+        };
+        println!(
+            "  {}  {} on {}{}",
+            style.format(entry.session.duration),
+            entry.instance,
+            entry.date(),
+            map
+        );
+    }
+    println!();
+}
+
+/// The current streak (ending today or yesterday) and the longest run of
+/// consecutive days played.
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (usize, usize) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        run = match previous {
+            Some(previous) if previous + Days::new(1) == day => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    let mut current = 0;
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        today - Days::new(1)
+    };
+    while days.contains(&day) {
+        current += 1;
+        day = day - Days::new(1);
+    }
+
+    (current, longest)
+}
+
+/// Sums session durations per period from `since` on, oldest first. Periods
+/// are identified by their first day, as returned by `period_start`.
+fn totals_by(
+    entries: &[Entry],
+    since: NaiveDate,
+    period_start: impl Fn(NaiveDate) -> NaiveDate,
+    label: impl Fn(NaiveDate) -> String,
+) -> Vec<(String, Duration)> {
+    let since = period_start(since);
+    let mut totals: BTreeMap<NaiveDate, Duration> = BTreeMap::new();
+    for entry in entries {
+        let start = period_start(entry.date());
+        if start >= since {
+            *totals.entry(start).or_default() += entry.session.duration;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(start, total)| (label(start), total))
+        .collect()
+}
+
+fn print_bar_chart(rows: &[(String, Duration)], style: DurationStyle) {
+    if rows.is_empty() {
+        println!("Nothing played in this range.");
+        return;
+    }
+
+    let max = rows
+        .iter()
+        .map(|(_, total)| total.as_secs())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let label_width = rows
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (label, total) in rows {
+        let width = ((total.as_secs() * BAR_WIDTH as u64 / max) as usize).max(1);
+        println!(
+            "{:<label_width$}  {}{}  {}",
+            label,
+            "█".repeat(width),
+            " ".repeat(BAR_WIDTH - width),
+            style.format(*total)
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(text: &str) -> NaiveDate {
+        text.parse().unwrap()
+    }
+
+    fn days(dates: &[&str]) -> BTreeSet<NaiveDate> {
+        dates.iter().map(|text| date(text)).collect()
+    }
+
+    fn session(date_text: &str, minutes: u64) -> SessionRecord {
+        let noon = date(date_text).and_hms_opt(12, 0, 0).unwrap();
+        SessionRecord {
+            started_at: Local.from_local_datetime(&noon).unwrap().into(),
+            duration: Duration::from_secs(minutes * 60),
+            raw_duration: None,
+            exit_code: Some(0),
+            map: None,
+        }
+    }
+
+    #[test]
+    fn streaks_count_consecutive_days() {
+        let played = days(&["2024-03-01", "2024-03-02", "2024-03-03", "2024-03-05"]);
+        assert_eq!(streaks(&played, date("2024-03-05")), (1, 3));
+        assert_eq!(streaks(&played, date("2024-03-06")), (1, 3));
+        assert_eq!(streaks(&played, date("2024-03-07")), (0, 3));
+        assert_eq!(streaks(&played, date("2024-03-04")), (3, 3));
+        assert_eq!(streaks(&BTreeSet::new(), date("2024-03-04")), (0, 0));
+    }
+
+    #[test]
+    fn streaks_run_across_month_ends() {
+        let played = days(&["2024-02-28", "2024-02-29", "2024-03-01"]);
+        assert_eq!(streaks(&played, date("2024-03-01")), (3, 3));
+    }
+
+    #[test]
+    fn totals_are_bucketed_by_period() {
+        let sessions = [
+            session("2024-01-31", 10),
+            session("2024-02-01", 20),
+            session("2024-02-29", 30),
+            session("2024-03-04", 40),
+        ];
+        let entries: Vec<Entry> = sessions
+            .iter()
+            .map(|session| Entry {
+                instance: "test",
+                session,
+            })
+            .collect();
+        let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+
+        let months = totals_by(
+            &entries,
+            date("2024-02-15"),
+            |date| date.with_day(1).unwrap(),
+            |date| date.to_string(),
+        );
+        assert_eq!(
+            months,
+            [
+                ("2024-02-01".to_string(), minutes(50)),
+                ("2024-03-01".to_string(), minutes(40)),
+            ]
+        );
+
+        // 2024-02-26 and 2024-03-04 are Mondays.
+        let weeks = totals_by(
+            &entries,
+            date("2024-02-27"),
+            |date| date - Days::new(date.weekday().num_days_from_monday() as u64),
+            |date| date.to_string(),
+        );
+        assert_eq!(
+            weeks,
+            [
+                ("2024-02-26".to_string(), minutes(30)),
+                ("2024-03-04".to_string(), minutes(40)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use std::ffi::{OsStr, OsString};
+use std::time::SystemTime;
+use sulphur_core::{Instance, Metadata, SulphurConfig};
+
+use crate::cli_config::{CliConfig, Template};
+use crate::input::{Confirm, Input, Select};
+use crate::instance_management::{
+    edit_single_instance, instance_names, instance_selection, validate_instance_name,
+    InstanceManagementExitState,
+};
+use crate::menu::{Menu, BACK_BUTTON};
+
+const NAME_PLACEHOLDER: &str = "{name}";
+
+fn replace_in(text: &OsStr, from: &str, to: &str) -> OsString {
+    match text.to_str() {
+        Some(text) => text.replace(from, to).into(),
+        None => text.to_os_string(),
+    }
+}
+
+pub fn template_management(
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    indexes: &[usize],
+) -> Result<()> {
+    loop {
+        match Select::new()
+            .with_prompt("Choose Action")
+            .items(Menu::TemplatesMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => {
+                if let Some(name) = template_selection(cli_config)? {
+                    create_from_template(config, cli_config, &name)?;
+                    return Ok(());
+                }
+            }
+            1 => save_as_template(config, cli_config, indexes)?,
+            2 => {
+                if let Some(name) = template_selection(cli_config)?
+                    && Confirm::new()
+                        .with_prompt(format!(
+                            "Are you sure you want to delete template '{}'?",
+                            name
+                        ))
+                        .default(false)
+                        .interact()?
+                {
+                    cli_config.templates.remove(&name);
+                }
+            }
+            3 => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+fn template_selection(cli_config: &CliConfig) -> Result<Option<String>> {
+    if cli_config.templates.is_empty() {
+        println!("No templates saved yet!");
+        return Ok(None);
+    }
+
+    let mut menu_items: Vec<&str> = cli_config.templates.keys().map(String::as_str).collect();
+    menu_items.push(BACK_BUTTON);
+
+    let selection = Select::new()
+        .with_prompt("Choose Template")
+        .default(0)
+        .items(&menu_items)
+        .interact()?;
+
+    if selection == menu_items.len() - 1 {
+        Ok(None)
+    } else {
+        Ok(Some(menu_items[selection].to_string()))
+    }
+}
+
+/// Stores an instance's setup as a template. Occurrences of the instance name
+/// in its parameters and save directory can be turned into `{name}`.
+fn save_as_template(
+    config: &SulphurConfig,
+    cli_config: &mut CliConfig,
+    indexes: &[usize],
+) -> Result<()> {
+    let Some(index) = instance_selection(config, cli_config, indexes)? else {
+        return Ok(());
+    };
+    let instance = &config.instances[index];
+    let mut gamedata = instance.gamedata.clone();
+
+    let template_name: String = Input::new()
+        .with_prompt("Enter template name")
+        .default(instance.metadata.name.clone())
+        .interact_text()?;
+
+    if Confirm::new()
+        .with_prompt(format!(
+            "Replace '{}' with {} in parameters and save directory?",
+            instance.metadata.name, NAME_PLACEHOLDER
+        ))
+        .default(true)
+        .interact()?
+    {
+        // Only whole path components and parameters, the name may well be a
+        // word that also appears elsewhere in them.
+        let name = OsStr::new(&instance.metadata.name);
+        if gamedata.savedir.file_name() == Some(name) {
+            gamedata.savedir.set_file_name(NAME_PLACEHOLDER);
+        }
+        for param in gamedata.additional_params.iter_mut() {
+            if param == name {
+                *param = NAME_PLACEHOLDER.into();
+            }
+        }
+    }
+
+    let template = Template {
+        gamedata,
+        extras: cli_config.extras(&instance.metadata.name).setup(),
+    };
+    cli_config.templates.insert(template_name.clone(), template);
+    println!("Template '{}' saved!", template_name);
+    Ok(())
+}
+
+fn create_from_template(
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    template_name: &str,
+) -> Result<()> {
+    let template = cli_config.templates[template_name].clone();
+
+    let taken_names = instance_names(config, None);
+    let name: String = Input::new()
+        .with_prompt("Enter instance name")
+        .validate_with(|input: &String| validate_instance_name(input, &taken_names))
+        .interact_text()?;
+
+    let mut gamedata = template.gamedata;
+    // Without the placeholder every instance would share the template's save directory.
+    let per_instance_savedir = gamedata
+        .savedir
+        .to_str()
+        .is_some_and(|savedir| savedir.contains(NAME_PLACEHOLDER));
+    gamedata.savedir = replace_in(gamedata.savedir.as_os_str(), NAME_PLACEHOLDER, &name).into();
+    for param in gamedata.additional_params.iter_mut() {
+        *param = replace_in(param, NAME_PLACEHOLDER, &name);
+    }
+
+    let mut instance = Instance {
+        metadata: Metadata {
+            name,
+            image: None,
+            playtime: Default::default(),
+            last_played: None,
+            last_session_duration: None,
+        },
+        gamedata,
+    };
+    if !per_instance_savedir {
+        instance.initialize_absolute_savedir()?;
+    }
+
+    let global_command = config.get_command();
+    if let InstanceManagementExitState::Some(new, mut extras) = edit_single_instance(
+        instance,
+        template.extras,
+        &cli_config.settings,
+        global_command.as_os_str(),
+        &taken_names,
+    )? {
+        extras.created = Some(SystemTime::now());
+        cli_config.add_extras(&new.metadata.name, extras)?;
+        config.instances.push(new);
+    }
+    Ok(())
+}
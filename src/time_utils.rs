@@ -0,0 +1,79 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Days, Local};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum TimeDisplay {
+    /// "3 hours ago", "yesterday at 21:40", switching to the date format
+    /// after a week.
+    #[default]
+    Relative,
+    Absolute,
+}
+
+impl TimeDisplay {
+    pub const ALL: [TimeDisplay; 2] = [TimeDisplay::Relative, TimeDisplay::Absolute];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeDisplay::Relative => "Relative (3 hours ago)",
+            TimeDisplay::Absolute => "Date format only",
+        }
+    }
+
+    pub fn format(&self, time: SystemTime, date_format: &str) -> String {
+        let time: DateTime<Local> = time.into();
+        match self {
+            TimeDisplay::Relative => relative_time(time, Local::now(), date_format),
+            TimeDisplay::Absolute => format_date(time, date_format),
+        }
+    }
+}
+
+/// Whether chrono understands every specifier in a strftime format.
+pub fn is_valid_date_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
+/// Formats with the user's format, falling back to the default one if it
+/// isn't valid (chrono panics on invalid formats).
+fn format_date(time: DateTime<Local>, date_format: &str) -> String {
+    let date_format = if is_valid_date_format(date_format) {
+        date_format
+    } else {
+        DEFAULT_DATE_FORMAT
+    };
+    time.format(date_format).to_string()
+}
+
+fn relative_time(time: DateTime<Local>, now: DateTime<Local>, date_format: &str) -> String {
+    let plural = |count: i64, unit: &str| {
+        format!(
+            "{} {}{} ago",
+            count,
+            unit,
+            if count == 1 { "" } else { "s" }
+        )
+    };
+
+    let elapsed = now.signed_duration_since(time);
+    let days = (now.date_naive() - time.date_naive()).num_days();
+    if elapsed.num_seconds() < 0 {
+        format_date(time, date_format)
+    } else if elapsed.num_minutes() < 1 {
+        "just now".to_string()
+    } else if elapsed.num_hours() < 1 {
+        plural(elapsed.num_minutes(), "minute")
+    } else if days == 0 {
+        plural(elapsed.num_hours(), "hour")
+    } else if now.date_naive() - Days::new(1) == time.date_naive() {
+        format!("yesterday at {}", time.format("%H:%M"))
+    } else if days < 7 {
+        plural(days, "day")
+    } else {
+        format_date(time, date_format)
+    }
+}
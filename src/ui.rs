@@ -1,60 +1,94 @@
 use anyhow::Result;
-use chrono::{DateTime, Local};
 use console::Style;
-use dialoguer::{theme::ColorfulTheme, Select};
-use sulphur_core::{Instance, SaveableDefaultPath, SulphurConfig};
+use std::collections::BTreeSet;
+use std::time::SystemTime;
+use sulphur_core::{Asset, Instance, SulphurConfig};
 use tabled::{builder::Builder, settings::Style as TabledStyle};
 
-use crate::duration_utils::ToString;
+use crate::asset_management::{hooks_management, launch_environment_management};
+use crate::cli_config::{CliConfig, CliSettings, InstanceExtras};
+use crate::command::{engine_argv, engine_version, get_full_command, print_dehacked_warnings};
+use crate::duration_utils::{parse_duration, DurationStyle};
+use crate::image_utils::{display_image, ImageProtocol};
+use crate::input::{Confirm, Input, Select};
+use crate::instance_diff::asset_lines;
 use crate::instance_management;
-use crate::menu::Menu;
-
-pub fn run_main_loop(config: &mut SulphurConfig) -> Result<()> {
-    let mut sort_by_playtime: bool = false;
+use crate::launch::{launch, run_pre_launch_hooks, Session};
+use crate::list_view::{iwad_names, InstanceFilter, SortKey};
+use crate::menu::{Menu, BACK_BUTTON};
+use crate::profile::{is_valid_profile_name, profile_names, ConfigLocation};
+use crate::stats;
+use crate::template_management;
+use crate::time_utils::{is_valid_date_format, TimeDisplay};
 
+pub fn run_main_loop(
+    location: &mut ConfigLocation,
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+) -> Result<()> {
     loop {
-        config.save()?;
-        let instances_order: Vec<usize> = config
-            .get_unplayed_instances()
-            .iter()
-            .cloned()
-            .chain(if sort_by_playtime {
-                config.get_indices_by_playtime()
-            } else {
-                config.get_indices_by_last_played()
-            })
-            .collect();
+        location.save(config, cli_config)?;
+        let instances_order = cli_config
+            .settings
+            .list_view
+            .instance_order(config, cli_config);
 
-        list_instances(config, &instances_order);
-        let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt("Main Menu")
+        list_instances(config, cli_config, &instances_order);
+        let prompt = match location {
+            ConfigLocation::Default => "Main Menu".to_string(),
+            _ => format!("Main Menu ({})", location.name()),
+        };
+        let selection = Select::new()
+            .with_prompt(prompt)
             .items(Menu::MainMenu.options())
             .default(0)
             .interact()?;
 
         match selection {
-            0 => run_instance(config, instances_order.as_slice())?,
-            1 => {
-                sort_by_playtime = !sort_by_playtime;
-                println!(
-                    "Sorting by {}",
-                    if sort_by_playtime {
-                        "playtime"
-                    } else {
-                        "last played"
-                    }
-                );
-            }
-            2 => {
-                let new = instance_management::create_new_instance()?;
-                if let instance_management::InstanceManagementExitState::Some(new) = new {
+            0 => run_instance(config, cli_config, instances_order.as_slice())?,
+            1 => choose_sort(config, cli_config)?,
+            2 => cli_config.settings.list_view.filter = choose_filter(config, cli_config)?,
+            3 => {
+                let new = instance_management::create_new_instance(
+                    &cli_config.settings,
+                    config.get_command().as_os_str(),
+                    &instance_management::instance_names(config, None),
+                )?;
+                if let instance_management::InstanceManagementExitState::Some(new, mut extras) = new
+                {
+                    extras.created = Some(SystemTime::now());
+                    cli_config.add_extras(&new.metadata.name, extras)?;
                     config.instances.push(new);
                 }
             }
-            3 => instance_management::manage_instances(config, instances_order.as_slice())?,
-            4 => instance_management::import_export(config, instances_order.as_slice())?,
-            5 => global_settings(config)?,
-            6 => {
+            4 => instance_management::duplicate_instance(
+                config,
+                cli_config,
+                instances_order.as_slice(),
+            )?,
+            5 => template_management::template_management(
+                config,
+                cli_config,
+                instances_order.as_slice(),
+            )?,
+            6 => instance_management::manage_instances(
+                location,
+                config,
+                cli_config,
+                instances_order.as_slice(),
+            )?,
+            7 => instance_management::compare_instances(
+                config,
+                cli_config,
+                instances_order.as_slice(),
+            )?,
+            8 => {
+                instance_management::import_export(config, cli_config, instances_order.as_slice())?
+            }
+            9 => instance_management::archived_instances(location, config, cli_config)?,
+            10 => stats::statistics(config, cli_config)?,
+            11 => global_settings(location, config, cli_config)?,
+            12 => {
                 return Ok(());
             }
             _ => {}
@@ -62,34 +96,404 @@ pub fn run_main_loop(config: &mut SulphurConfig) -> Result<()> {
     }
 }
 
-fn global_settings(config: &mut SulphurConfig) -> Result<()> {
+fn global_settings(
+    location: &mut ConfigLocation,
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+) -> Result<()> {
     let mut selection = 0;
     loop {
         let menu_items = Menu::GlobalSettingsMenu.options();
-        selection = Select::with_theme(&ColorfulTheme::default())
+        selection = Select::new()
             .with_prompt("Choose Action")
-            .items(&menu_items)
+            .items(menu_items)
             .default(selection)
             .interact()?;
 
         match selection {
             0 => {
-                let input_string = dialoguer::Input::with_theme(&ColorfulTheme::default())
+                let input_string = Input::new()
                     .with_prompt("Enter command to run gzdoom")
                     .default(config.gzdoom_command.to_string_lossy().to_string())
                     .interact_text()?;
                 config.gzdoom_command = input_string.into();
             }
-            1 => return Ok(()),
+            1 => {
+                let settings = &mut cli_config.settings;
+                settings.patches_as_file = !settings.patches_as_file;
+                println!(
+                    "DeHackEd patches will be loaded with {}",
+                    if settings.patches_as_file {
+                        "-file"
+                    } else {
+                        "-deh/-bex"
+                    }
+                );
+            }
+            2 => {
+                let settings = &mut cli_config.settings;
+                launch_environment_management(&mut settings.wrappers, &mut settings.env)?
+            }
+            3 => hooks_management(&mut cli_config.settings.hooks)?,
+            4 => {
+                let input: String = Input::new()
+                    .with_prompt("Use the compact list above how many instances? (empty for never)")
+                    .default(
+                        cli_config
+                            .settings
+                            .compact_list_threshold
+                            .map(|threshold| threshold.to_string())
+                            .unwrap_or_default(),
+                    )
+                    .allow_empty(true)
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        if input.trim().is_empty() || input.trim().parse::<usize>().is_ok() {
+                            Ok(())
+                        } else {
+                            Err("Enter a number or leave empty")
+                        }
+                    })
+                    .interact_text()?;
+                cli_config.settings.compact_list_threshold = input.trim().parse().ok();
+            }
+            5 => {
+                let names: Vec<&str> = ImageProtocol::ALL.iter().map(ImageProtocol::name).collect();
+                let current = ImageProtocol::ALL
+                    .iter()
+                    .position(|&protocol| protocol == cli_config.settings.image_protocol)
+                    .unwrap_or(0);
+                let selection = Select::new()
+                    .with_prompt("Show instance images using")
+                    .items(&names)
+                    .default(current)
+                    .interact()?;
+                cli_config.settings.image_protocol = ImageProtocol::ALL[selection];
+            }
+            6 => {
+                let duration_style = cli_config.settings.duration_style;
+                let policy = &mut cli_config.settings.idle_policy;
+                let input: String = Input::new()
+                    .with_prompt("Count a single session for at most (e.g. 3h, empty for no limit)")
+                    .default(
+                        policy
+                            .max_session
+                            .map(|max| duration_style.format(max))
+                            .unwrap_or_default(),
+                    )
+                    .allow_empty(true)
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        if input.trim().is_empty() || parse_duration(input).is_some() {
+                            Ok(())
+                        } else {
+                            Err("Enter a duration like 2h 30m or leave empty")
+                        }
+                    })
+                    .interact_text()?;
+                policy.max_session = parse_duration(&input);
+                policy.exclude_stopped = Confirm::new()
+                    .with_prompt("Leave out time the engine is stopped (SIGSTOP)?")
+                    .default(policy.exclude_stopped)
+                    .interact()?;
+            }
+            7 => {
+                let names: Vec<&str> = DurationStyle::ALL.iter().map(DurationStyle::name).collect();
+                let current = DurationStyle::ALL
+                    .iter()
+                    .position(|&style| style == cli_config.settings.duration_style)
+                    .unwrap_or(0);
+                let selection = Select::new()
+                    .with_prompt("Show durations as")
+                    .items(&names)
+                    .default(current)
+                    .interact()?;
+                cli_config.settings.duration_style = DurationStyle::ALL[selection];
+            }
+            8 => {
+                let settings = &mut cli_config.settings;
+                let names: Vec<&str> = TimeDisplay::ALL.iter().map(TimeDisplay::name).collect();
+                let current = TimeDisplay::ALL
+                    .iter()
+                    .position(|&display| display == settings.time_display)
+                    .unwrap_or(0);
+                let selection = Select::new()
+                    .with_prompt("Show times as")
+                    .items(&names)
+                    .default(current)
+                    .interact()?;
+                settings.time_display = TimeDisplay::ALL[selection];
+
+                let date_format: String = Input::new()
+                    .with_prompt("Date format (strftime, empty for the default)")
+                    .default(settings.date_format.clone().unwrap_or_default())
+                    .allow_empty(true)
+                    .validate_with(|input: &String| -> Result<(), &str> {
+                        if is_valid_date_format(input) {
+                            Ok(())
+                        } else {
+                            Err("Not a valid strftime format")
+                        }
+                    })
+                    .interact_text()?;
+                settings.date_format = Some(date_format).filter(|format| !format.is_empty());
+            }
+            // The settings shown belong to the profile left behind.
+            9 if switch_profile(location, config, cli_config)? => return Ok(()),
+            10 => return Ok(()),
             _ => {}
         }
     }
 }
 
-fn list_instances(config: &SulphurConfig, indexes: &[usize]) {
+/// Saves the current configs and loads those of another profile, creating it
+/// if needed. Returns whether the profile changed.
+fn switch_profile(
+    location: &mut ConfigLocation,
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+) -> Result<bool> {
+    let names = profile_names()?;
+    let mut locations = vec![ConfigLocation::Default];
+    locations.extend(names.iter().cloned().map(ConfigLocation::Profile));
+    if let ConfigLocation::File(_) = location {
+        locations.push(location.clone());
+    }
+
+    let mut items: Vec<String> = locations
+        .iter()
+        .map(|option| {
+            if option == location {
+                format!("{} (current)", option.name())
+            } else {
+                option.name()
+            }
+        })
+        .collect();
+    items.push("New Profile".to_string());
+    items.push(BACK_BUTTON.to_string());
+    let current = locations
+        .iter()
+        .position(|option| option == location)
+        .unwrap_or(0);
+    let selection = Select::new()
+        .with_prompt("Switch to Profile")
+        .items(&items)
+        .default(current)
+        .interact()?;
+
+    let new_location = match selection {
+        index if index < locations.len() => locations[index].clone(),
+        index if index == locations.len() => {
+            let name: String = Input::new()
+                .with_prompt("Profile name")
+                .validate_with(|input: &String| -> Result<(), &str> {
+                    if !is_valid_profile_name(input) {
+                        Err("Use only letters, numbers, '-' and '_'")
+                    } else if names.contains(input) {
+                        Err("A profile with this name already exists")
+                    } else {
+                        Ok(())
+                    }
+                })
+                .interact_text()?;
+            ConfigLocation::Profile(name)
+        }
+        _ => return Ok(false),
+    };
+    if new_location == *location {
+        return Ok(false);
+    }
+
+    location.save(config, cli_config)?;
+    let (new_config, new_cli_config) = new_location.load()?;
+    *config = new_config;
+    *cli_config = new_cli_config;
+    *location = new_location;
+    // Saving right away makes a new profile show up in the list.
+    location.save(config, cli_config)?;
+    println!("Switched to profile '{}'", location.name());
+    Ok(true)
+}
+
+fn choose_sort(config: &mut SulphurConfig, cli_config: &mut CliConfig) -> Result<()> {
+    let list_view = &mut cli_config.settings.list_view;
+    let names: Vec<&str> = SortKey::ALL.iter().map(SortKey::name).collect();
+    let current = SortKey::ALL
+        .iter()
+        .position(|&key| key == list_view.sort)
+        .unwrap_or(0);
+    let selection = Select::new()
+        .with_prompt("Sort by")
+        .items(&names)
+        .default(current)
+        .interact()?;
+    let sort = SortKey::ALL[selection];
+
+    // The custom order is the config order, which has no direction.
+    let descending = if sort == SortKey::Custom {
+        false
+    } else {
+        let directions = ["Ascending", "Descending"];
+        Select::new()
+            .with_prompt("Direction")
+            .items(&directions)
+            .default(sort.descending_by_default() as usize)
+            .interact()?
+            == 1
+    };
+
+    list_view.sort = sort;
+    list_view.descending = descending;
+    if sort == SortKey::Custom {
+        arrange_instances(config, cli_config)?;
+    }
+    Ok(())
+}
+
+/// Moves instances around in the config, which is the order the custom sort
+/// mode shows them in. Moves are relative to the list as currently shown and
+/// stay among the favourites or the other instances, as those are listed apart.
+fn arrange_instances(config: &mut SulphurConfig, cli_config: &CliConfig) -> Result<()> {
+    loop {
+        let order = cli_config
+            .settings
+            .list_view
+            .instance_order(config, cli_config);
+        let Some(index) = instance_management::instance_selection(config, cli_config, &order)?
+        else {
+            return Ok(());
+        };
+        let position = order.iter().position(|&other| other == index).unwrap();
+        let favourite = |index: usize| {
+            cli_config
+                .instances
+                .get(&config.instances[index].metadata.name)
+                .is_some_and(|extras| extras.favourite)
+        };
+        let same_partition = |&other: &usize| favourite(other) == favourite(index);
+        let first = order.iter().position(same_partition).unwrap();
+        let last = order.iter().rposition(same_partition).unwrap();
+
+        let target = match Select::new()
+            .with_prompt(format!("Move '{}'", config.instances[index].metadata.name))
+            .items(Menu::ArrangeMenu.options())
+            .default(0)
+            .interact()?
+        {
+            0 => position.saturating_sub(1).max(first),
+            1 => (position + 1).min(last),
+            2 => first,
+            3 => last,
+            _ => return Ok(()),
+        };
+
+        // Swap through the listed neighbours one at a time, so instances
+        // that aren't shown keep their place.
+        if target < position {
+            for step in (target..position).rev() {
+                config.instances.swap(order[step], order[step + 1]);
+            }
+        } else {
+            for step in position..target {
+                config.instances.swap(order[step], order[step + 1]);
+            }
+        }
+    }
+}
+
+fn choose_filter(config: &SulphurConfig, cli_config: &CliConfig) -> Result<InstanceFilter> {
+    let selection = Select::new()
+        .with_prompt("Show")
+        .items(Menu::FilterMenu.options())
+        .default(0)
+        .interact()?;
+
+    let extras = cli_config.instances.values();
+    let (prompt, choices): (&str, BTreeSet<String>) = match selection {
+        1 => return Ok(InstanceFilter::Unplayed),
+        2 => (
+            "Choose Tag",
+            extras.flat_map(|extras| extras.tags.clone()).collect(),
+        ),
+        3 => (
+            "Choose Group",
+            extras.filter_map(|extras| extras.group.clone()).collect(),
+        ),
+        4 => (
+            "Choose IWAD",
+            config.instances.iter().flat_map(iwad_names).collect(),
+        ),
+        5 => (
+            "Choose Engine",
+            config
+                .instances
+                .iter()
+                .map(|instance| {
+                    cli_config
+                        .instances
+                        .get(&instance.metadata.name)
+                        .and_then(|extras| extras.engine.clone())
+                        .unwrap_or_default()
+                })
+                .collect(),
+        ),
+        _ => return Ok(InstanceFilter::All),
+    };
+    if choices.is_empty() {
+        println!("Nothing to filter by yet!");
+        return Ok(InstanceFilter::All);
+    }
+
+    let choices: Vec<String> = choices.into_iter().collect();
+    let labels: Vec<&str> = choices
+        .iter()
+        .map(|choice| {
+            if choice.is_empty() {
+                "(global command)"
+            } else {
+                choice
+            }
+        })
+        .collect();
+    let choice = Select::new()
+        .with_prompt(prompt)
+        .items(&labels)
+        .default(0)
+        .interact()?;
+
+    let choice = choices[choice].clone();
+    Ok(match selection {
+        2 => InstanceFilter::Tag(choice),
+        3 => InstanceFilter::Group(choice),
+        4 => InstanceFilter::Iwad(choice),
+        _ => InstanceFilter::Engine(choice),
+    })
+}
+
+fn list_instances(config: &SulphurConfig, cli_config: &CliConfig, indexes: &[usize]) {
     let index_style = Style::new().bold().fg(console::Color::Cyan);
     let separator_style = Style::new().fg(console::Color::White).dim();
 
+    if cli_config
+        .settings
+        .compact_list_threshold
+        .is_some_and(|threshold| indexes.len() > threshold)
+    {
+        for (display_index, &actual_index) in indexes.iter().enumerate() {
+            let instance = &config.instances[actual_index];
+            println!(
+                "{} {}",
+                index_style.apply_to(format!("{:>3}.", display_index + 1)),
+                compact_instance_line(
+                    instance,
+                    &cli_config.extras(&instance.metadata.name),
+                    &cli_config.settings,
+                )
+            );
+        }
+        println!();
+        return;
+    }
+
     for (display_index, &actual_index) in indexes.iter().enumerate() {
         if display_index > 0 {
             println!("{}", separator_style.apply_to("─".repeat(80)));
@@ -100,7 +504,12 @@ fn list_instances(config: &SulphurConfig, indexes: &[usize]) {
             index_style.apply_to(format!("Instance #{}", display_index + 1)),
         );
 
-        let table = instance_table(&config.instances[actual_index]);
+        let instance = &config.instances[actual_index];
+        let table = instance_table(
+            instance,
+            &cli_config.extras(&instance.metadata.name),
+            &cli_config.settings,
+        );
         for line in table.lines() {
             println!("\t\t{}", line);
         }
@@ -108,25 +517,69 @@ fn list_instances(config: &SulphurConfig, indexes: &[usize]) {
     }
 }
 
-fn instance_table(instance: &Instance) -> String {
-    let last_played = &format!("{}", {
-        if let Some(a) = instance.metadata.last_played {
-            let date_time: DateTime<Local> = a.into();
-            format!(
-                "{}  (lasted {})",
-                date_time.format("%c").to_string(),
-                instance.metadata.last_session_duration.unwrap().to_string()
-            )
-        } else {
-            "Never".to_string()
+fn compact_instance_line(
+    instance: &Instance,
+    extras: &InstanceExtras,
+    settings: &CliSettings,
+) -> String {
+    let detail_style = Style::new().dim();
+    let mut line = format!(
+        "{}{}",
+        if extras.favourite { "★ " } else { "" },
+        instance.metadata.name
+    );
+    let mut details = vec![settings.duration_style.format(instance.metadata.playtime)];
+    if !extras.tags.is_empty() {
+        details.push(format!("[{}]", extras.tags.join(", ")));
+    }
+    if let Some(group) = &extras.group {
+        details.push(group.clone());
+    }
+    line.push_str(&format!("  {}", detail_style.apply_to(details.join(" · "))));
+    line
+}
+
+fn instance_table(instance: &Instance, extras: &InstanceExtras, settings: &CliSettings) -> String {
+    let last_played = match instance.metadata.last_played {
+        Some(last_played) => {
+            let mut text = settings.format_time(last_played);
+            if let Some(duration) = instance.metadata.last_session_duration {
+                text.push_str(&format!(
+                    "  (lasted {})",
+                    settings.duration_style.format(duration)
+                ));
+            }
+            text
         }
-    },);
+        None => "Never".to_string(),
+    };
+
+    let name = if extras.favourite {
+        format!("★ {}", instance.metadata.name)
+    } else {
+        instance.metadata.name.clone()
+    };
 
-    let data = [
-        ("Name", &instance.metadata.name),
-        ("Playtime", &instance.metadata.playtime.to_string()),
+    let mut data = vec![
+        ("Name", name),
+        (
+            "Playtime",
+            settings.duration_style.format(instance.metadata.playtime),
+        ),
         ("Last Played", last_played),
     ];
+    if !extras.tags.is_empty() {
+        data.push(("Tags", extras.tags.join(", ")));
+    }
+    if let Some(group) = &extras.group {
+        data.push(("Group", group.clone()));
+    }
+    if let Some(first_line) = extras.notes.lines().next() {
+        data.push((
+            "Notes",
+            notes_preview(first_line, extras.notes.lines().count() > 1),
+        ));
+    }
 
     let mut table = Builder::new();
     for (name, value) in data.iter() {
@@ -139,11 +592,148 @@ fn instance_table(instance: &Instance) -> String {
     table.to_string()
 }
 
-fn run_instance(config: &mut SulphurConfig, indexes: &[usize]) -> Result<()> {
-    if let Some(instance_index) = instance_management::instance_selection(config, indexes)? {
-        let command = config.get_command();
-        let instance = &mut config.instances[instance_index];
-        instance.run(instance.get_full_command(command.as_os_str()));
+fn notes_preview(first_line: &str, has_more_lines: bool) -> String {
+    const MAX_PREVIEW_CHARS: usize = 40;
+
+    let mut preview: String = first_line.chars().take(MAX_PREVIEW_CHARS).collect();
+    if has_more_lines || first_line.chars().count() > MAX_PREVIEW_CHARS {
+        preview.push('…');
+    }
+    preview
+}
+
+pub fn show_instance_details(instance: &Instance, extras: &InstanceExtras, settings: &CliSettings) {
+    let title_style = Style::new().bold().fg(console::Color::Cyan);
+
+    if let Some(image) = &instance.metadata.image
+        && let Err(error) = display_image(image, settings.image_protocol)
+    {
+        println!("Could not show '{}': {}", image.to_string_lossy(), error);
+    }
+
+    println!("{}", instance_table(instance, extras, settings));
+
+    let indented_assets = |assets: Vec<&Asset>| -> Vec<String> {
+        asset_lines(assets, true)
+            .into_iter()
+            .map(|line| format!("  {}", line))
+            .collect()
+    };
+    let sections = [
+        (
+            "IWADs",
+            indented_assets(instance.gamedata.iwads.iter().map(|iwad| &iwad.0).collect()),
+        ),
+        (
+            "Mods",
+            indented_assets(instance.gamedata.mods.iter().map(|mod_| &mod_.0).collect()),
+        ),
+        (
+            "DeHackEd Patches",
+            indented_assets(extras.patches.iter().map(|patch| &patch.0).collect()),
+        ),
+        (
+            "Save Directory",
+            vec![format!("  {}", instance.gamedata.savedir.to_string_lossy())],
+        ),
+        (
+            "Additional Parameters",
+            instance
+                .gamedata
+                .additional_params
+                .iter()
+                .map(|param| format!("  {}", param.to_string_lossy()))
+                .collect(),
+        ),
+        (
+            "Engine",
+            vec![format!(
+                "  {}",
+                extras.engine.as_deref().unwrap_or("Global GZDoom command")
+            )],
+        ),
+        (
+            "Notes",
+            extras
+                .notes
+                .lines()
+                .map(|line| format!("  {}", line))
+                .collect(),
+        ),
+    ];
+
+    for (title, lines) in sections {
+        if lines.is_empty() {
+            continue;
+        }
+        println!("{}", title_style.apply_to(title));
+        for line in lines {
+            println!("{}", line);
+        }
+    }
+    println!();
+}
+
+fn run_instance(
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    indexes: &[usize],
+) -> Result<()> {
+    if let Some(instance_index) =
+        instance_management::instance_selection(config, cli_config, indexes)?
+        && let Err(error) = launch_instance(config, cli_config, instance_index, true)
+    {
+        println!(
+            "Launch of '{}' aborted: {}",
+            config.instances[instance_index].metadata.name, error
+        );
     }
     Ok(())
 }
+
+/// Launches an instance and records the session. Unless `interactive`, nothing
+/// is asked and a changed engine version is only reported. Returns `None` if
+/// the user called the launch off.
+pub fn launch_instance(
+    config: &mut SulphurConfig,
+    cli_config: &mut CliConfig,
+    index: usize,
+    interactive: bool,
+) -> Result<Option<Session>> {
+    let command = config.get_command();
+    let instance = &mut config.instances[index];
+    let mut extras = cli_config.extras(&instance.metadata.name);
+
+    let version = engine_version(&engine_argv(&extras, command.as_os_str()));
+    if let (Some(last_version), Some(version)) = (&extras.engine_version, &version) {
+        let message = format!(
+            "Engine version changed from '{}' to '{}' since the last launch.",
+            last_version, version
+        );
+        if last_version != version {
+            if !interactive {
+                println!("Warning: {}", message);
+            } else if !Confirm::new()
+                .with_prompt(format!("{} Continue?", message))
+                .default(true)
+                .interact()?
+            {
+                return Ok(None);
+            }
+        }
+    }
+
+    // Only once the launch goes ahead, so post-exit hooks always follow.
+    run_pre_launch_hooks(instance, &extras, &cli_config.settings)?;
+    print_dehacked_warnings(instance, &extras);
+    let full_command =
+        get_full_command(instance, &extras, &cli_config.settings, command.as_os_str());
+    let session = launch(instance, &mut extras, &cli_config.settings, full_command)?;
+
+    if session.exit_status.success() && version.is_some() {
+        extras.engine_version = version;
+    }
+    let name = instance.metadata.name.clone();
+    cli_config.set_extras(&name, &name, extras)?;
+    Ok(Some(session))
+}
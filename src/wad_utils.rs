@@ -0,0 +1,90 @@
+use anyhow::{bail, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const HEADER_SIZE: usize = 12;
+const DIRECTORY_ENTRY_SIZE: usize = 16;
+
+pub struct Lump {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Checks that `size` bytes at `offset` lie within the file, before allocating
+/// anything based on a size read from it.
+fn check_range(file: &File, path: &Path, offset: u64, size: u64) -> Result<()> {
+    let fits = offset
+        .checked_add(size)
+        .is_some_and(|end| end <= file.metadata().map_or(0, |metadata| metadata.len()));
+    if !fits {
+        bail!("'{}' is truncated or corrupt", path.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Reads the lump directory of an IWAD/PWAD file.
+pub fn read_lumps(path: &Path) -> Result<Vec<Lump>> {
+    let mut file = File::open(path)?;
+    let mut header = [0u8; HEADER_SIZE];
+    file.read_exact(&mut header)?;
+
+    if &header[0..4] != b"IWAD" && &header[0..4] != b"PWAD" {
+        bail!("'{}' is not a WAD file", path.to_string_lossy());
+    }
+
+    let lump_count = read_u32(&header, 4) as usize;
+    let directory_offset = read_u32(&header, 8) as u64;
+    check_range(
+        &file,
+        path,
+        directory_offset,
+        (lump_count * DIRECTORY_ENTRY_SIZE) as u64,
+    )?;
+
+    let mut directory = vec![0u8; lump_count * DIRECTORY_ENTRY_SIZE];
+    file.seek(SeekFrom::Start(directory_offset))?;
+    file.read_exact(&mut directory)?;
+
+    Ok(directory
+        .chunks_exact(DIRECTORY_ENTRY_SIZE)
+        .map(|entry| Lump {
+            offset: read_u32(entry, 0),
+            size: read_u32(entry, 4),
+            name: String::from_utf8_lossy(&entry[8..16])
+                .trim_end_matches('\0')
+                .to_uppercase(),
+        })
+        .collect())
+}
+
+/// Whether the file is a WAD containing a lump with the given name.
+/// Anything that can't be read as a WAD (pk3, zip, ...) counts as not containing it.
+pub fn has_lump(path: &Path, name: &str) -> bool {
+    read_lumps(path)
+        .map(|lumps| lumps.iter().any(|lump| lump.name == name))
+        .unwrap_or(false)
+}
+
+/// Contents of the last lump with the given name, which is the one the engine uses.
+pub fn read_lump(path: &Path, name: &str) -> Result<Option<Vec<u8>>> {
+    let Some(lump) = read_lumps(path)?
+        .into_iter()
+        .rev()
+        .find(|lump| lump.name == name)
+    else {
+        return Ok(None);
+    };
+
+    let mut file = File::open(path)?;
+    check_range(&file, path, lump.offset as u64, lump.size as u64)?;
+    let mut data = vec![0u8; lump.size as usize];
+    file.seek(SeekFrom::Start(lump.offset as u64))?;
+    file.read_exact(&mut data)?;
+    Ok(Some(data))
+}
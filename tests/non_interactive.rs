@@ -0,0 +1,92 @@
+//! Running without a terminal: the usage message, `--list` and launching an
+//! instance by name.
+
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use std::os::unix::fs::PermissionsExt;
+
+/// Creates "Test Instance" with a stand-in engine that records its arguments
+/// in `$HOME/launched` and each version probe in `$HOME/probes`.
+fn sandbox_with_instance(name: &str) -> Sandbox {
+    let sandbox = Sandbox::new(name);
+    let engine = sandbox.home.join("engine.sh");
+    std::fs::write(
+        &engine,
+        "#!/bin/sh\n\
+         [ \"$1\" = --version ] && { echo probe >> \"$HOME/probes\"; echo 1.0; exit 0; }\n\
+         printf '%s\\n' \"$@\" > \"$HOME/launched\"\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&engine, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = sandbox.run(&format!(
+        "Configure Global Settings\nSet GZDoom Path\n{}\nBack\n\
+         Create New Instance\nTest Instance\ny\nSave Changes\nSave & Exit\n",
+        engine.to_string_lossy()
+    ));
+    assert!(output.status.success(), "{}", stderr(&output));
+    sandbox
+}
+
+#[test]
+fn help() {
+    let sandbox = Sandbox::new("help");
+    let output = sandbox.run_with(&["--help"], "");
+
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("Usage"));
+}
+
+#[test]
+fn menus_need_a_terminal() {
+    let sandbox = Sandbox::new("no-terminal");
+    let output = sandbox.run_with(&[], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("interactive terminal"));
+    assert!(stderr(&output).contains("--list"));
+}
+
+#[test]
+fn list() {
+    let sandbox = sandbox_with_instance("list");
+    let output = sandbox.run_with(&["--list"], "");
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Test Instance\n");
+}
+
+#[test]
+fn launch_by_name() {
+    let sandbox = sandbox_with_instance("launch");
+    let output = sandbox.run_with(&["Test Instance"], "");
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let arguments = std::fs::read_to_string(sandbox.home.join("launched")).unwrap();
+    assert!(arguments.contains("-savedir"));
+    let cli_config =
+        std::fs::read_to_string(sandbox.home.join(".config/sulphur/cli.toml")).unwrap();
+    assert!(cli_config.contains("sessions"));
+}
+
+#[test]
+fn engine_version_is_probed_once() {
+    let sandbox = sandbox_with_instance("version-cache");
+    for _ in 0..2 {
+        let output = sandbox.run_with(&["Test Instance"], "");
+        assert!(output.status.success(), "{}", stderr(&output));
+    }
+
+    let probes = std::fs::read_to_string(sandbox.home.join("probes")).unwrap();
+    assert_eq!(probes.lines().count(), 1);
+}
+
+#[test]
+fn launch_unknown_instance() {
+    let sandbox = Sandbox::new("unknown-instance");
+    let output = sandbox.run_with(&["Nothing Here"], "");
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("no instance named"));
+}
@@ -0,0 +1,65 @@
+//! Config files given with `--config` and named profiles.
+
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+
+#[test]
+fn config_file() {
+    let sandbox = Sandbox::new("config-file");
+    let config = sandbox.home.join("custom.toml");
+    let config = config.to_str().unwrap();
+
+    let output = sandbox.run_with(
+        &["--config", config, "--script", "-"],
+        "Create New Instance\nElsewhere\ny\nSave Changes\nSave & Exit\n",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(sandbox.home.join("custom.cli.toml").exists());
+
+    let output = sandbox.run_with(&["--config", config, "--list"], "");
+    assert_eq!(stdout(&output), "Elsewhere\n");
+    let output = sandbox.run_with(&["--list"], "");
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn broken_config_file() {
+    let sandbox = Sandbox::new("broken-config");
+    let config = sandbox.home.join("broken.toml");
+    std::fs::write(&config, "instances = [").unwrap();
+
+    let output = sandbox.run_with(&["--config", config.to_str().unwrap(), "--list"], "");
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("couldn't read"));
+    assert_eq!(std::fs::read_to_string(&config).unwrap(), "instances = [");
+}
+
+#[test]
+fn profiles() {
+    let sandbox = Sandbox::new("profiles");
+    let output = sandbox.run(
+        "Configure Global Settings\nSwitch Profile\nNew Profile\nspeedrun\n\
+         Create New Instance\nSpeedrun Instance\ny\nSave Changes\nSave & Exit\n",
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = sandbox.run_with(&["--profile", "speedrun", "--list"], "");
+    assert_eq!(stdout(&output), "Speedrun Instance\n");
+    let output = sandbox.run_with(&["--list"], "");
+    assert_eq!(stdout(&output), "");
+
+    // With a profile around, the menus start by asking for one.
+    let output = sandbox.run("speedrun\nSave & Exit\n");
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Speedrun Instance"));
+}
+
+#[test]
+fn unknown_profile() {
+    let sandbox = Sandbox::new("unknown-profile");
+    let output = sandbox.run_with(&["--profile", "work", "--list"], "");
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("no profile named 'work'"));
+}
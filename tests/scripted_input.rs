@@ -0,0 +1,103 @@
+//! Drives whole menu journeys through the binary with `--script -`, inside a
+//! throwaway home directory.
+
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+
+/// Answers creating "Test Instance" and leaving its edit menu open.
+const CREATE_INSTANCE: &str = "\
+Create New Instance
+Test Instance
+; update the save directory
+y
+";
+
+#[test]
+fn create_instance() {
+    let sandbox = Sandbox::new("create");
+    let output = sandbox.run(&format!("{}Save Changes\nSave & Exit\n", CREATE_INSTANCE));
+
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("Test Instance"));
+    let cli_config =
+        std::fs::read_to_string(sandbox.home.join(".config/sulphur/cli.toml")).unwrap();
+    assert!(cli_config.contains("Test Instance"));
+}
+
+#[test]
+fn add_mod() {
+    let sandbox = Sandbox::new("add-mod");
+    std::fs::write(sandbox.home.join("test.wad"), b"PWAD\0\0\0\0\x0c\0\0\0").unwrap();
+
+    let output = sandbox.run(&format!(
+        "{}Edit Mods\ntest.wad\n; don't move the file\nn\nBack\n\
+         Show Details\nSave Changes\nSave & Exit\n",
+        CREATE_INSTANCE
+    ));
+
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("test.wad"));
+    assert!(sandbox.home.join("test.wad").exists());
+}
+
+#[test]
+fn search_by_disabled_file() {
+    let sandbox = Sandbox::new("search-file");
+    std::fs::write(sandbox.home.join("test.wad"), b"PWAD\0\0\0\0\x0c\0\0\0").unwrap();
+
+    let output = sandbox.run(&format!(
+        "{}Edit Mods\ntest.wad\nn\nToggle Mod\n@1\nBack\nSave Changes\n\
+         Edit Instance\nSearch by File Name\nTEST\nTest Instance\nBack\ny\nBack\n\
+         Save & Exit\n",
+        CREATE_INSTANCE
+    ));
+
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(stdout(&output).contains("› test instance  (test.wad)"));
+}
+
+#[test]
+fn export_instance() {
+    let sandbox = Sandbox::new("export");
+    let output = sandbox.run(&format!(
+        "{}Save Changes\n\
+         Import/Export Instance\nExport as .brimpkg\nTest Instance\n./\n\
+         ; transfer saves, transfer playtime\nn\nn\n\
+         Back\nSave & Exit\n",
+        CREATE_INSTANCE
+    ));
+
+    assert!(output.status.success(), "{}", stdout(&output));
+    assert!(sandbox.home.join("Test Instance.brimpkg").exists());
+}
+
+#[test]
+fn unfinished_script_fails() {
+    let sandbox = Sandbox::new("unfinished");
+    let output = sandbox.run("Create New Instance\n");
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("input script ended"));
+}
+
+#[test]
+fn unknown_choice_fails() {
+    let sandbox = Sandbox::new("unknown-choice");
+    let output = sandbox.run("Launch Rockets\n");
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("not one of the choices"));
+}
+
+#[test]
+fn duplicate_name_fails() {
+    let sandbox = Sandbox::new("duplicate-name");
+    let output = sandbox.run(&format!(
+        "{}Save Changes\nCreate New Instance\nTest Instance\n",
+        CREATE_INSTANCE
+    ));
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("already exists"));
+}